@@ -1,11 +1,18 @@
-use clap::{App, SubCommand, ArgMatches};
+use clap::{App, SubCommand, ArgMatches, Arg};
 use crate::util;
-use crate::util::{make_input_arg, decode_hex, encode_hex, CmdError};
+use crate::util::{make_input_arg, read_hex_input, decode_hex, encode_hex, CmdError};
+use crate::crypto::keccak256;
+use crate::address::label_address;
+use crate::units::MAX_UINT256_DIGITS;
 use std::str::FromStr;
 use std::error;
 use std::fmt;
-use std::io::Write;
-use num_bigint::{BigUint, BigInt};
+use std::io;
+use std::io::{BufRead, Write};
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+use num_bigint::{BigUint, BigInt, Sign};
 
 #[derive(Debug)]
 pub enum ABIError {
@@ -15,6 +22,18 @@ pub enum ABIError {
     InvalidFieldDefinition,
     InvalidValue(String),
     ByteSizeMismatch,
+    ArrayLengthMismatch(usize, usize),
+    TupleLengthMismatch(usize, usize),
+    UnsupportedPacked(String),
+    AmbiguousPacked,
+    InvalidSignature(String),
+    UnknownSelector(String),
+    SelectorMismatch(String, String),
+    InvalidABIJson(String),
+    InvalidJsonInput(String),
+    UnknownFunction(String),
+    AmbiguousFunction(String, Vec<String>),
+    InvalidData(String),
     Unimplemented,
 }
 
@@ -27,6 +46,18 @@ impl fmt::Display for ABIError {
             ABIError::InvalidFieldDefinition => write!(f, "invalid field definition"),
             ABIError::InvalidValue(s) => write!(f, "{} is an invalid value", s),
             ABIError::ByteSizeMismatch => write!(f, "the size of the byte field specified does not match its actual size"),
+            ABIError::ArrayLengthMismatch(exp, recv) => write!(f, "expected {} array elements but got {}", exp, recv),
+            ABIError::TupleLengthMismatch(exp, recv) => write!(f, "expected {} tuple components but got {}", exp, recv),
+            ABIError::UnsupportedPacked(t) => write!(f, "{} is not supported in packed encoding", t),
+            ABIError::AmbiguousPacked => write!(f, "packed data with more than one dynamic type cannot be decoded unambiguously"),
+            ABIError::InvalidSignature(s) => write!(f, "{} is an invalid signature", s),
+            ABIError::UnknownSelector(s) => write!(f, "no known signature matches selector {}", s),
+            ABIError::SelectorMismatch(exp, recv) => write!(f, "selector mismatch, expected {} but got {}", exp, recv),
+            ABIError::InvalidABIJson(s) => write!(f, "invalid ABI JSON: {}", s),
+            ABIError::InvalidJsonInput(s) => write!(f, "invalid JSON input: {}", s),
+            ABIError::UnknownFunction(s) => write!(f, "no function named {} in the ABI", s),
+            ABIError::AmbiguousFunction(s, candidates) => write!(f, "{} is overloaded; specify one of {}", s, candidates.join(", ")),
+            ABIError::InvalidData(s) => write!(f, "invalid ABI data: {}", s),
             ABIError::Unimplemented => write!(f, "this feature is unimplemented")
         }
     }
@@ -36,7 +67,7 @@ impl error::Error for ABIError {}
 
 impl From<std::io::Error> for ABIError {
     fn from(e: std::io::Error) -> Self {
-        ABIError::IOError(String::from(e.to_string()))
+        ABIError::IOError(e.to_string())
     }
 }
 
@@ -64,10 +95,14 @@ enum ABIField {
     String,
     Bytes,
     BytesN(u16),
+    Function,
     IntN(u16),
     UintN(u16),
     FixedN(u16, u16),
     UFixedN(u16, u16),
+    Array(Box<ABIField>),
+    FixedArray(Box<ABIField>, usize),
+    Tuple(Vec<ABIField>),
 }
 
 impl ABIField {
@@ -111,7 +146,7 @@ impl ABIField {
         fixed_field_size_from_name("fixed", s).and_then(|mn| {
             let (m, n) = mn;
 
-            if m < 8 || m > 256 || m % 8 != 0 {
+            if !(8..=256).contains(&m) || m % 8 != 0 {
                 return Err(ABIError::InvalidSize(m));
             }
             if n > 80 {
@@ -123,10 +158,10 @@ impl ABIField {
     }
 
     fn parse_ufixed(s: &str) -> Result<ABIField, ABIError> {
-        fixed_field_size_from_name("fixed", s).and_then(|mn| {
+        fixed_field_size_from_name("ufixed", s).and_then(|mn| {
             let (m, n) = mn;
 
-            if m < 8 || m > 256 || m % 8 != 0 {
+            if !(8..=256).contains(&m) || m % 8 != 0 {
                 return Err(ABIError::InvalidSize(mn.0));
             }
             if n > 80 {
@@ -137,28 +172,537 @@ impl ABIField {
         })
     }
 
-    fn encode_packed(&self, data: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
+    fn parse_array(s: &str) -> Result<ABIField, ABIError> {
+        let inner = &s[..s.len() - 2];
+        Ok(ABIField::Array(Box::new(ABIField::from_str(inner)?)))
+    }
+
+    fn parse_fixed_array(s: &str) -> Result<ABIField, ABIError> {
+        let open = s.rfind('[').ok_or(ABIError::InvalidFieldDefinition)?;
+        let len: usize = s[open + 1..s.len() - 1].parse()?;
+        if len == 0 {
+            return Err(ABIError::InvalidFieldDefinition);
+        }
+        Ok(ABIField::FixedArray(Box::new(ABIField::from_str(&s[..open])?), len))
+    }
+
+    fn parse_tuple(s: &str) -> Result<ABIField, ABIError> {
+        let inner = s.trim_start_matches("tuple");
+        let components = split_group(inner, '(', ')').map_err(|_| ABIError::InvalidFieldDefinition)?;
+        if components.is_empty() {
+            return Err(ABIError::InvalidFieldDefinition);
+        }
+
+        let fields = components.into_iter().map(ABIField::from_str).collect::<Result<Vec<ABIField>, ABIError>>()?;
+        Ok(ABIField::Tuple(fields))
+    }
+
+    fn is_dynamic(&self) -> bool {
+        match self {
+            ABIField::String | ABIField::Bytes | ABIField::Array(_) => true,
+            ABIField::FixedArray(inner, _) => inner.is_dynamic(),
+            ABIField::Tuple(fields) => fields.iter().any(|f| f.is_dynamic()),
+            _ => false,
+        }
+    }
+
+    fn is_composite(&self) -> bool {
+        matches!(self, ABIField::Array(_) | ABIField::FixedArray(_, _) | ABIField::Tuple(_))
+    }
+
+    /// The number of bytes the value occupies in the head of its enclosing encoding.
+    fn head_size(&self) -> usize {
         match self {
-            ABIField::Address => encode_packed_address(data, buf),
-            ABIField::String => encode_packed_string(data, buf),
-            ABIField::UintN(size) => encode_packed_uintn(data, size, buf),
-            ABIField::IntN(size) => encode_packed_intn(data, size, buf),
-            ABIField::Bytes => encode_packed_bytes(data, buf),
-            ABIField::BytesN(size) => encode_packed_bytesn(data, size, buf),
-            ABIField::Boolean => encode_packed_bool(data, buf),
+            ABIField::FixedArray(inner, len) if !self.is_dynamic() => inner.head_size() * len,
+            ABIField::Tuple(fields) if !self.is_dynamic() => fields.iter().map(|f| f.head_size()).sum(),
+            _ => 32,
+        }
+    }
+
+    /// Converts a JSON value into a value of this type. Arrays and tuples are
+    /// given as JSON arrays; other values as strings, numbers, or booleans.
+    fn parse_json_value(&self, v: &Value) -> Result<ABIValue, ABIError> {
+        match (self, v) {
+            (ABIField::Array(inner), Value::Array(items)) => {
+                let values = items.iter().map(|i| inner.parse_json_value(i)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Array(values))
+            }
+            (ABIField::FixedArray(inner, len), Value::Array(items)) => {
+                if items.len() != *len {
+                    return Err(ABIError::ArrayLengthMismatch(*len, items.len()));
+                }
+                let values = items.iter().map(|i| inner.parse_json_value(i)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Array(values))
+            }
+            (ABIField::Tuple(fields), Value::Array(items)) => {
+                if items.len() != fields.len() {
+                    return Err(ABIError::TupleLengthMismatch(fields.len(), items.len()));
+                }
+                let values = fields.iter().zip(items).map(|(f, i)| f.parse_json_value(i)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Tuple(values))
+            }
+            (_, Value::String(s)) if self.is_composite() => self.parse_value(s),
+            (_, Value::String(s)) => self.parse_literal(s),
+            (_, Value::Number(n)) => self.parse_literal(&n.to_string()),
+            (_, Value::Bool(b)) => self.parse_literal(&b.to_string()),
+            _ => Err(ABIError::InvalidJsonInput(format!("{} is not a valid {}", v, self))),
+        }
+    }
+
+    /// Checks that a numeric value fits within the field's bit width. Fixed-point
+    /// values are checked after scaling.
+    fn check_range(&self, value: &BigInt, input: &str) -> Result<(), ABIError> {
+        let (bits, signed, decimals) = match self {
+            ABIField::UintN(size) => (*size, false, 0),
+            ABIField::IntN(size) => (*size, true, 0),
+            ABIField::UFixedN(size, n) => (*size, false, *n),
+            ABIField::FixedN(size, n) => (*size, true, *n),
+            _ => return Ok(()),
+        };
+
+        let one = BigInt::from(1u8);
+        let (min, max) = if signed {
+            let half = &one << (bits as usize - 1);
+            (-half.clone(), half - &one)
+        } else {
+            (BigInt::from(0u8), (&one << bits as usize) - &one)
+        };
+        if *value < min || *value > max {
+            return Err(ABIError::InvalidValue(format!("{} ({} allows {} to {})",
+                input, self, format_fixed(&min, decimals), format_fixed(&max, decimals))));
+        }
+        Ok(())
+    }
+
+    /// Parses a value written in the `type:value` input syntax. Scalar values
+    /// may be double-quoted, and a backslash escapes the next character.
+    fn parse_value(&self, data: &str) -> Result<ABIValue, ABIError> {
+        match self {
+            ABIField::Array(inner) => {
+                let items = split_group(data, '[', ']')?;
+                let values = items.iter().map(|item| inner.parse_value(item)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Array(values))
+            }
+            ABIField::FixedArray(inner, len) => {
+                let items = split_group(data, '[', ']')?;
+                if items.len() != *len {
+                    return Err(ABIError::ArrayLengthMismatch(*len, items.len()));
+                }
+                let values = items.iter().map(|item| inner.parse_value(item)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Array(values))
+            }
+            ABIField::Tuple(fields) => {
+                let items = split_group(data, '(', ')')?;
+                if items.len() != fields.len() {
+                    return Err(ABIError::TupleLengthMismatch(fields.len(), items.len()));
+                }
+                let values = fields.iter().zip(items).map(|(f, item)| f.parse_value(item)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Tuple(values))
+            }
+            _ => self.parse_literal(&unquote(data)),
+        }
+    }
+
+    /// Parses a scalar value that has already been unquoted.
+    fn parse_literal(&self, data: &str) -> Result<ABIValue, ABIError> {
+        match self {
+            ABIField::Address => parse_address(data),
+            ABIField::Boolean => parse_bool(data),
+            ABIField::String => Ok(ABIValue::String(String::from(data))),
+            ABIField::UintN(_) => {
+                let value = parse_integer(data)?;
+                self.check_range(&value, data)?;
+                Ok(ABIValue::Uint(value.to_biguint().ok_or_else(|| ABIError::InvalidValue(String::from(data)))?))
+            }
+            ABIField::IntN(_) => {
+                let value = parse_integer(data)?;
+                self.check_range(&value, data)?;
+                Ok(ABIValue::Int(value))
+            }
+            ABIField::Bytes => Ok(ABIValue::Bytes(parse_bytes_value(data)?)),
+            ABIField::BytesN(size) => parse_bytesn(data, size),
+            ABIField::Function => parse_function(data),
+            ABIField::FixedN(_, n) | ABIField::UFixedN(_, n) => {
+                let value = parse_fixed_value(data, *n)?;
+                self.check_range(&value, data)?;
+                Ok(ABIValue::Fixed(value, *n))
+            }
+            _ => Err(ABIError::InvalidValue(String::from(data))),
+        }
+    }
+
+    fn encode_packed(&self, value: &ABIValue, buf: &mut Vec<u8>) -> Result<(), ABIError> {
+        match (self, value) {
+            (ABIField::Address, ABIValue::Address(a)) => buf.write_all(a)?,
+            (ABIField::Boolean, ABIValue::Boolean(b)) => buf.write_all(&[*b as u8])?,
+            (ABIField::String, ABIValue::String(s)) => buf.write_all(s.as_bytes())?,
+            (ABIField::UintN(size), ABIValue::Uint(n)) => encode_packed_uintn(n, size, buf)?,
+            (ABIField::IntN(size), ABIValue::Int(n)) => encode_packed_signed(n, size, buf)?,
+            (ABIField::FixedN(size, _), ABIValue::Fixed(n, _)) => encode_packed_signed(n, size, buf)?,
+            (ABIField::UFixedN(size, _), ABIValue::Fixed(n, _)) => {
+                let n = n.to_biguint().ok_or_else(|| ABIError::InvalidValue(n.to_string()))?;
+                encode_packed_uintn(&n, size, buf)?
+            }
+            (ABIField::Bytes, ABIValue::Bytes(b)) | (ABIField::BytesN(_), ABIValue::Bytes(b)) | (ABIField::Function, ABIValue::Bytes(b)) => buf.write_all(b)?,
+            (ABIField::Array(inner), ABIValue::Array(items)) | (ABIField::FixedArray(inner, _), ABIValue::Array(items)) => {
+                // Array elements are padded to 32 bytes even in packed mode,
+                // and like Solidity we only support arrays of elementary types.
+                if inner.is_dynamic() || inner.is_composite() {
+                    return Err(ABIError::UnsupportedPacked(self.to_string()));
+                }
+                for item in items {
+                    buf.write_all(&inner.encode(item)?)?;
+                }
+            }
+            (ABIField::Tuple(_), _) => return Err(ABIError::UnsupportedPacked(self.to_string())),
+            _ => return Err(ABIError::Unimplemented)
+        }
+
+        Ok(())
+    }
+
+    /// Encodes a value using the standard ABI encoding. Static values produce
+    /// their head; dynamic values produce the data their head's offset points to.
+    fn encode(&self, value: &ABIValue) -> Result<Vec<u8>, ABIError> {
+        match (self, value) {
+            (ABIField::Address, ABIValue::Address(a)) => Ok(left_pad(a, 0)),
+            (ABIField::Boolean, ABIValue::Boolean(b)) => Ok(left_pad(&[*b as u8], 0)),
+            (ABIField::UintN(_), ABIValue::Uint(n)) => encode_word(&n.to_bytes_be(), 0, &n.to_string()),
+            (ABIField::IntN(_), ABIValue::Int(n)) => {
+                let fill = if n.sign() == Sign::Minus { 0xff } else { 0 };
+                encode_word(&n.to_signed_bytes_be(), fill, &n.to_string())
+            }
+            (ABIField::FixedN(_, _), ABIValue::Fixed(n, decimals)) => {
+                let fill = if n.sign() == Sign::Minus { 0xff } else { 0 };
+                encode_word(&n.to_signed_bytes_be(), fill, &format_fixed(n, *decimals))
+            }
+            (ABIField::UFixedN(_, _), ABIValue::Fixed(n, decimals)) => {
+                encode_word(&n.to_bytes_be().1, 0, &format_fixed(n, *decimals))
+            }
+            (ABIField::BytesN(_), ABIValue::Bytes(b)) | (ABIField::Function, ABIValue::Bytes(b)) => Ok(right_pad(b)),
+            (ABIField::String, ABIValue::String(s)) => Ok(encode_dynamic_bytes(s.as_bytes())),
+            (ABIField::Bytes, ABIValue::Bytes(b)) => Ok(encode_dynamic_bytes(b)),
+            (ABIField::Array(inner), ABIValue::Array(items)) => {
+                let mut out = encode_usize(items.len());
+                let fields: Vec<&ABIField> = items.iter().map(|_| inner.as_ref()).collect();
+                out.extend(encode_sequence(&fields, items)?);
+                Ok(out)
+            }
+            (ABIField::FixedArray(inner, len), ABIValue::Array(items)) => {
+                if items.len() != *len {
+                    return Err(ABIError::ArrayLengthMismatch(*len, items.len()));
+                }
+                let fields: Vec<&ABIField> = items.iter().map(|_| inner.as_ref()).collect();
+                encode_sequence(&fields, items)
+            }
+            (ABIField::Tuple(fields), ABIValue::Tuple(items)) => {
+                if items.len() != fields.len() {
+                    return Err(ABIError::TupleLengthMismatch(fields.len(), items.len()));
+                }
+                let fields: Vec<&ABIField> = fields.iter().collect();
+                encode_sequence(&fields, items)
+            }
             _ => Err(ABIError::Unimplemented)
         }
     }
+
+    /// The number of bytes the value occupies in packed encoding, or `None`
+    /// if its size depends on the value.
+    fn packed_size(&self) -> Result<Option<usize>, ABIError> {
+        match self {
+            ABIField::Address => Ok(Some(20)),
+            ABIField::Boolean => Ok(Some(1)),
+            ABIField::BytesN(size) => Ok(Some(*size as usize)),
+            ABIField::Function => Ok(Some(24)),
+            ABIField::UintN(size) | ABIField::IntN(size) | ABIField::FixedN(size, _) | ABIField::UFixedN(size, _) => Ok(Some(*size as usize / 8)),
+            ABIField::String | ABIField::Bytes => Ok(None),
+            ABIField::Array(inner) | ABIField::FixedArray(inner, _) if inner.is_dynamic() || inner.is_composite() => {
+                Err(ABIError::UnsupportedPacked(self.to_string()))
+            }
+            ABIField::Array(_) => Ok(None),
+            ABIField::FixedArray(_, len) => Ok(Some(32 * len)),
+            ABIField::Tuple(_) => Err(ABIError::UnsupportedPacked(self.to_string())),
+        }
+    }
+
+    /// Decodes a value from exactly the bytes of its packed encoding.
+    fn decode_packed(&self, data: &[u8]) -> Result<ABIValue, ABIError> {
+        match self {
+            ABIField::Address => Ok(ABIValue::Address(data.to_vec())),
+            ABIField::Boolean => match data {
+                [0] => Ok(ABIValue::Boolean(false)),
+                [1] => Ok(ABIValue::Boolean(true)),
+                _ => Err(ABIError::InvalidData(String::from("invalid boolean value"))),
+            },
+            ABIField::UintN(_) => Ok(ABIValue::Uint(BigUint::from_bytes_be(data))),
+            ABIField::IntN(_) => Ok(ABIValue::Int(BigInt::from_signed_bytes_be(data))),
+            ABIField::FixedN(_, n) => Ok(ABIValue::Fixed(BigInt::from_signed_bytes_be(data), *n)),
+            ABIField::UFixedN(_, n) => Ok(ABIValue::Fixed(BigInt::from_bytes_be(Sign::Plus, data), *n)),
+            ABIField::Bytes | ABIField::BytesN(_) | ABIField::Function => Ok(ABIValue::Bytes(data.to_vec())),
+            ABIField::String => String::from_utf8(data.to_vec())
+                .map(ABIValue::String)
+                .map_err(|_| ABIError::InvalidData(String::from("string is not valid UTF-8"))),
+            ABIField::Array(inner) | ABIField::FixedArray(inner, _) => {
+                if !data.len().is_multiple_of(32) {
+                    return Err(ABIError::InvalidData(format!("{} bytes is not a whole number of array elements", data.len())));
+                }
+                let values = data.chunks(32).map(|word| inner.decode(word, 0, 0)).collect::<Result<Vec<ABIValue>, ABIError>>()?;
+                Ok(ABIValue::Array(values))
+            }
+            ABIField::Tuple(_) => Err(ABIError::UnsupportedPacked(self.to_string())),
+        }
+    }
+
+    /// Labels the words of data encoding this value, whose head is at `pos`,
+    /// with the part of the value they hold. Malformed offsets and lengths
+    /// leave the words they point to unlabeled.
+    fn label_words(&self, data: &[u8], base: usize, pos: usize, name: &str, labels: &mut Vec<String>) {
+        let mut label = |word: usize, text: String| {
+            if let Some(l) = labels.get_mut(word) {
+                *l = text;
+            }
+        };
+
+        if !self.is_dynamic() {
+            match self {
+                ABIField::FixedArray(inner, len) => {
+                    for i in 0..*len {
+                        inner.label_words(data, base, pos + i * inner.head_size(), &format!("{}[{}]", name, i), labels);
+                    }
+                }
+                ABIField::Tuple(fields) => {
+                    let mut field_pos = pos;
+                    for (i, field) in fields.iter().enumerate() {
+                        field.label_words(data, base, field_pos, &format!("{}.{}", name, i), labels);
+                        field_pos += field.head_size();
+                    }
+                }
+                _ => label(pos / 32, format!("{} ({})", name, self)),
+            }
+            return;
+        }
+
+        label(pos / 32, format!("{} offset", name));
+        let start = match read_word(data, pos).and_then(read_usize) {
+            Ok(offset) => base + offset,
+            Err(_) => return,
+        };
+        match self {
+            ABIField::String | ABIField::Bytes => {
+                label(start / 32, format!("{} length", name));
+                if let Ok(len) = read_word(data, start).and_then(read_usize) {
+                    for word in 0..len.div_ceil(32) {
+                        label(start / 32 + 1 + word, format!("{} data", name));
+                    }
+                }
+            }
+            ABIField::Array(inner) => {
+                label(start / 32, format!("{} length", name));
+                let len = read_word(data, start).and_then(read_usize).unwrap_or(0).min(data.len() / 32);
+                for i in 0..len {
+                    inner.label_words(data, start + 32, start + 32 + i * inner.head_size(), &format!("{}[{}]", name, i), labels);
+                }
+            }
+            ABIField::FixedArray(inner, len) => {
+                for i in 0..*len {
+                    inner.label_words(data, start, start + i * inner.head_size(), &format!("{}[{}]", name, i), labels);
+                }
+            }
+            ABIField::Tuple(fields) => {
+                let mut field_pos = start;
+                for (i, field) in fields.iter().enumerate() {
+                    field.label_words(data, start, field_pos, &format!("{}.{}", name, i), labels);
+                    field_pos += field.head_size();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Decodes the value whose head is at `pos`. Offsets of dynamic values
+    /// are relative to `base`, the start of the enclosing encoding.
+    fn decode(&self, data: &[u8], base: usize, pos: usize) -> Result<ABIValue, ABIError> {
+        let head = read_word(data, pos)?;
+        match self {
+            ABIField::Address => Ok(ABIValue::Address(head[12..].to_vec())),
+            ABIField::Boolean => match BigUint::from_bytes_be(head) {
+                ref n if *n == BigUint::from(0u8) => Ok(ABIValue::Boolean(false)),
+                ref n if *n == BigUint::from(1u8) => Ok(ABIValue::Boolean(true)),
+                _ => Err(ABIError::InvalidData(String::from("invalid boolean value"))),
+            },
+            ABIField::UintN(_) => Ok(ABIValue::Uint(BigUint::from_bytes_be(head))),
+            ABIField::IntN(_) => Ok(ABIValue::Int(BigInt::from_signed_bytes_be(head))),
+            ABIField::FixedN(_, n) => Ok(ABIValue::Fixed(BigInt::from_signed_bytes_be(head), *n)),
+            ABIField::UFixedN(_, n) => Ok(ABIValue::Fixed(BigInt::from_bytes_be(Sign::Plus, head), *n)),
+            ABIField::BytesN(size) => Ok(ABIValue::Bytes(head[..*size as usize].to_vec())),
+            ABIField::Function => Ok(ABIValue::Bytes(head[..24].to_vec())),
+            ABIField::Array(inner) => {
+                let start = base + read_usize(head)?;
+                let len = read_usize(read_word(data, start)?)?;
+                if len > data.len() / 32 {
+                    return Err(ABIError::InvalidData(format!("array length {} exceeds the available data", len)));
+                }
+                let fields: Vec<&ABIField> = (0..len).map(|_| inner.as_ref()).collect();
+                Ok(ABIValue::Array(decode_sequence(&fields, data, start + 32)?))
+            }
+            ABIField::FixedArray(inner, len) => {
+                let fields: Vec<&ABIField> = (0..*len).map(|_| inner.as_ref()).collect();
+                let start = if self.is_dynamic() { base + read_usize(head)? } else { pos };
+                Ok(ABIValue::Array(decode_sequence(&fields, data, start)?))
+            }
+            ABIField::Tuple(fields) => {
+                let fields: Vec<&ABIField> = fields.iter().collect();
+                let start = if self.is_dynamic() { base + read_usize(head)? } else { pos };
+                Ok(ABIValue::Tuple(decode_sequence(&fields, data, start)?))
+            }
+            ABIField::Bytes | ABIField::String => {
+                let start = base + read_usize(head)?;
+                let len = read_usize(read_word(data, start)?)?;
+                let bytes = read_bytes(data, start + 32, len)?.to_vec();
+                if let ABIField::String = self {
+                    String::from_utf8(bytes)
+                        .map(ABIValue::String)
+                        .map_err(|_| ABIError::InvalidData(String::from("string is not valid UTF-8")))
+                } else {
+                    Ok(ABIValue::Bytes(bytes))
+                }
+            }
+        }
+    }
+}
+
+pub enum ABIValue {
+    Address(Vec<u8>),
+    Boolean(bool),
+    String(String),
+    Bytes(Vec<u8>),
+    Uint(BigUint),
+    Int(BigInt),
+    Fixed(BigInt, u16),
+    Array(Vec<ABIValue>),
+    Tuple(Vec<ABIValue>),
+}
+
+impl ABIValue {
+    /// Formats the value for people rather than scripts: like `Display`, but
+    /// each address is followed by its label from the local label store.
+    pub fn labeled(&self) -> String {
+        match self {
+            ABIValue::Address(a) => label_address(a),
+            ABIValue::Array(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.labeled()).collect();
+                format!("[{}]", items.join(", "))
+            }
+            ABIValue::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.labeled()).collect();
+                format!("({})", items.join(", "))
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ABIValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ABIValue::Address(a) => write!(f, "{}", encode_hex(a)),
+            ABIValue::Boolean(b) => write!(f, "{}", b),
+            ABIValue::String(s) => write!(f, "{}", s),
+            ABIValue::Bytes(b) => write!(f, "{}", encode_hex(b)),
+            ABIValue::Uint(n) => write!(f, "{}", n),
+            ABIValue::Int(n) => write!(f, "{}", n),
+            ABIValue::Fixed(n, decimals) => write!(f, "{}", format_fixed(n, *decimals)),
+            ABIValue::Array(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            ABIValue::Tuple(items) => {
+                let items: Vec<String> = items.iter().map(|v| v.to_string()).collect();
+                write!(f, "({})", items.join(", "))
+            }
+        }
+    }
+}
+
+/// Formats a fixed-point integer with `decimals` digits after the decimal point.
+fn format_fixed(n: &BigInt, decimals: u16) -> String {
+    let digits = n.to_str_radix(10).trim_start_matches('-').to_string();
+    let decimals = decimals as usize;
+    let sign = if n.sign() == Sign::Minus { "-" } else { "" };
+    if decimals == 0 {
+        return format!("{}{}", sign, digits);
+    }
+
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (int, frac) = padded.split_at(padded.len() - decimals);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        format!("{}{}", sign, int)
+    } else {
+        format!("{}{}.{}", sign, int, frac)
+    }
+}
+
+fn read_bytes(data: &[u8], pos: usize, len: usize) -> Result<&[u8], ABIError> {
+    pos.checked_add(len)
+        .and_then(|end| data.get(pos..end))
+        .ok_or_else(|| ABIError::InvalidData(format!("{} bytes at offset {} are out of bounds", len, pos)))
+}
+
+fn read_word(data: &[u8], pos: usize) -> Result<&[u8], ABIError> {
+    read_bytes(data, pos, 32)
+}
+
+fn read_usize(word: &[u8]) -> Result<usize, ABIError> {
+    if word[..24].iter().any(|b| *b != 0) {
+        return Err(ABIError::InvalidData(String::from("offset or length is too large")));
+    }
+
+    let mut buf: [u8; 8] = [0; 8];
+    buf.copy_from_slice(&word[24..]);
+    Ok(u64::from_be_bytes(buf) as usize)
+}
+
+impl fmt::Display for ABIField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ABIField::Address => write!(f, "address"),
+            ABIField::Boolean => write!(f, "bool"),
+            ABIField::String => write!(f, "string"),
+            ABIField::Bytes => write!(f, "bytes"),
+            ABIField::BytesN(size) => write!(f, "bytes{}", size),
+            ABIField::Function => write!(f, "function"),
+            ABIField::IntN(size) => write!(f, "int{}", size),
+            ABIField::UintN(size) => write!(f, "uint{}", size),
+            ABIField::FixedN(m, n) => write!(f, "fixed{}x{}", m, n),
+            ABIField::UFixedN(m, n) => write!(f, "ufixed{}x{}", m, n),
+            ABIField::Array(inner) => write!(f, "{}[]", inner),
+            ABIField::FixedArray(inner, len) => write!(f, "{}[{}]", inner, len),
+            ABIField::Tuple(fields) => {
+                let fields: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+                write!(f, "({})", fields.join(","))
+            }
+        }
+    }
 }
 
 impl FromStr for ABIField {
     type Err = ABIError;
 
     fn from_str(s: &str) -> Result<ABIField, ABIError> {
+        if s.ends_with("[]") {
+            return ABIField::parse_array(s);
+        } else if s.ends_with(']') {
+            return ABIField::parse_fixed_array(s);
+        } else if s.ends_with(')') {
+            return ABIField::parse_tuple(s);
+        }
+
         match s {
             "address" => Ok(ABIField::Address),
             "bool" => Ok(ABIField::Boolean),
             "string" => Ok(ABIField::String),
+            "function" => Ok(ABIField::Function),
             name => {
                 if s.starts_with("bytes") {
                     ABIField::parse_bytes(s)
@@ -180,7 +724,7 @@ impl FromStr for ABIField {
 
 fn field_size_from_name(prefix: &str, name: &str) -> Result<u16, ABIError> {
     let stripped = name.trim_start_matches(prefix);
-    if stripped.len() == 0 {
+    if stripped.is_empty() {
         return Ok(0);
     }
 
@@ -190,8 +734,9 @@ fn field_size_from_name(prefix: &str, name: &str) -> Result<u16, ABIError> {
 
 fn fixed_field_size_from_name(prefix: &str, name: &str) -> Result<(u16, u16), ABIError> {
     let stripped = name.trim_start_matches(prefix);
-    if stripped.len() == 0 {
-        return Err(ABIError::InvalidFieldDefinition);
+    if stripped.is_empty() {
+        // fixed and ufixed are aliases for fixed128x18 and ufixed128x18.
+        return Ok((128, 18));
     }
 
     let sizes: Vec<&str> = stripped.split("x").collect();
@@ -202,111 +747,1663 @@ fn fixed_field_size_from_name(prefix: &str, name: &str) -> Result<(u16, u16), AB
     let m = sizes[0].parse()?;
     let n = sizes[1].parse()?;
 
-    return Ok((m, n));
+    Ok((m, n))
 }
 
-fn encode_packed_address(data: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
+fn parse_address(data: &str) -> Result<ABIValue, ABIError> {
     let dec = decode_hex(data)?;
     if dec.len() != 20 {
         return Err(ABIError::InvalidValue(String::from("invalid address")));
     }
-    buf.write(dec.as_slice())?;
-    Ok(())
+    Ok(ABIValue::Address(dec))
 }
 
-fn encode_packed_bool(data: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
+fn parse_bool(data: &str) -> Result<ABIValue, ABIError> {
     if data == "true" {
-        let val: [u8; 1] = [0x01];
-        buf.write(&val)?;
+        Ok(ABIValue::Boolean(true))
     } else if data == "false" {
-        let val: [u8; 1] = [0x00];
-        buf.write(&val)?;
+        Ok(ABIValue::Boolean(false))
     } else {
-        return Err(ABIError::InvalidValue(String::from("invalid boolean value")));
+        Err(ABIError::InvalidValue(String::from("invalid boolean value")))
     }
+}
 
-    Ok(())
+/// Parses hex-encoded bytes, or reads them from a file when given as `@path`.
+fn parse_bytes_value(data: &str) -> Result<Vec<u8>, ABIError> {
+    match data.strip_prefix('@') {
+        Some(path) => fs::read(path).map_err(|e| ABIError::IOError(format!("{}: {}", path, e))),
+        None => Ok(decode_hex(data)?),
+    }
 }
 
-fn encode_packed_string(data: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    buf.write(data.to_string().as_bytes())?;
-    Ok(())
+/// Parses a function reference given either as 24 hex-encoded bytes or as
+/// `<address>:<selector>`, where the selector may also be a signature.
+fn parse_function(data: &str) -> Result<ABIValue, ABIError> {
+    let out = match data.split_once(':') {
+        Some((address, selector)) => {
+            let mut out = decode_hex(address.trim())?;
+            if out.len() != 20 {
+                return Err(ABIError::InvalidValue(format!("{} (function addresses are 20 bytes)", address)));
+            }
+            let selector = selector.trim();
+            if selector.contains('(') {
+                out.extend(FunctionSignature::from_str(selector)?.selector().iter());
+            } else {
+                out.extend(decode_hex(selector)?);
+            }
+            out
+        }
+        None => decode_hex(data)?,
+    };
+    if out.len() != 24 {
+        return Err(ABIError::InvalidValue(format!("{} (functions are a 20-byte address and a 4-byte selector)", data)));
+    }
+    Ok(ABIValue::Bytes(out))
 }
 
-fn encode_packed_uintn(data: &str, size: &u16, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    let num = BigUint::from_str(data)?;
+fn parse_bytesn(data: &str, size: &u16) -> Result<ABIValue, ABIError> {
+    let data_buf = parse_bytes_value(data)?;
+    if data_buf.len() != *size as usize {
+        return Err(ABIError::ByteSizeMismatch);
+    }
+
+    Ok(ABIValue::Bytes(data_buf))
+}
+
+fn encode_packed_uintn(num: &BigUint, size: &u16, buf: &mut Vec<u8>) -> Result<(), ABIError> {
     let mut b = num.to_bytes_be();
-    let mut pad: Vec<u8> = vec![0; (size / 8) as usize - b.len()];
+    let pad_len = ((size / 8) as usize).checked_sub(b.len())
+        .ok_or_else(|| ABIError::InvalidValue(num.to_string()))?;
+    let mut pad: Vec<u8> = vec![0; pad_len];
     pad.append(&mut b);
-    buf.write(pad.as_slice())?;
+    buf.write_all(pad.as_slice())?;
     Ok(())
 }
 
-fn encode_packed_intn(data: &str, size: &u16, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    let num = BigInt::from_str(data)?;
-    let mut b = num.to_signed_bytes_be();
-    let mut pad: Vec<u8> = vec![0; (size / 8) as usize - b.len()];
-    pad.append(&mut b);
-    buf.write(pad.as_slice())?;
+/// Encodes a signed number into `size` bits of two's complement, sign-extending
+/// negative values with 0xff.
+fn encode_packed_signed(num: &BigInt, size: &u16, buf: &mut Vec<u8>) -> Result<(), ABIError> {
+    let b = num.to_signed_bytes_be();
+    let pad_len = ((size / 8) as usize).checked_sub(b.len())
+        .ok_or_else(|| ABIError::InvalidValue(num.to_string()))?;
+    let fill = if num.sign() == Sign::Minus { 0xff } else { 0 };
+    buf.write_all(&vec![fill; pad_len])?;
+    buf.write_all(&b)?;
     Ok(())
 }
 
-fn encode_packed_bytes(data: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    let mut data_buf = decode_hex(data)?;
-    buf.append(&mut data_buf);
-    Ok(())
+/// Parses an integer literal. Besides plain decimals, this accepts `_` digit
+/// separators, `0x`-prefixed hex, and scientific notation such as `1e18` or
+/// `1.5e6` as long as the result is a whole number.
+fn parse_integer(data: &str) -> Result<BigInt, ABIError> {
+    let invalid = || ABIError::InvalidValue(String::from(data));
+    let cleaned = data.trim().replace('_', "");
+    let (negative, digits) = match cleaned.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, cleaned.as_str()),
+    };
+
+    let magnitude = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        BigInt::parse_bytes(hex.as_bytes(), 16).ok_or_else(invalid)?
+    } else if let Some(e) = digits.find(['e', 'E']) {
+        let exp: usize = digits[e + 1..].parse().map_err(|_| invalid())?;
+        let (int, frac) = match digits[..e].find('.') {
+            Some(i) => (&digits[..i], &digits[i + 1..e]),
+            None => (&digits[..e], ""),
+        };
+        let frac = frac.trim_end_matches('0');
+        if frac.len() > exp || (int.is_empty() && frac.is_empty()) {
+            return Err(invalid());
+        }
+        let mantissa = format!("{}{}", int, frac);
+        let mantissa = mantissa.trim_start_matches('0');
+        if mantissa.is_empty() {
+            BigInt::from(0u8)
+        } else {
+            // Check the digit count before materializing the exponent's zeros,
+            // since no 256-bit value has more digits than a uint256.
+            let zeros = exp - frac.len();
+            if mantissa.len().saturating_add(zeros) > MAX_UINT256_DIGITS {
+                return Err(invalid());
+            }
+            let scaled = format!("{}{}", mantissa, "0".repeat(zeros));
+            BigInt::parse_bytes(scaled.as_bytes(), 10).ok_or_else(invalid)?
+        }
+    } else {
+        BigInt::parse_bytes(digits.as_bytes(), 10).ok_or_else(invalid)?
+    };
+
+    if digits.starts_with('-') {
+        return Err(invalid());
+    }
+    Ok(if negative { -magnitude } else { magnitude })
 }
 
-fn encode_packed_bytesn(data: &str, size: &u16, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    let mut data_buf = decode_hex(data)?;
-    if data_buf.len() != *size as usize {
-        return Err(ABIError::ByteSizeMismatch);
+/// Parses a decimal string such as `-1.25` into an integer scaled by 10^decimals.
+fn parse_fixed_value(data: &str, decimals: u16) -> Result<BigInt, ABIError> {
+    let invalid = || ABIError::InvalidValue(String::from(data));
+    let (int, frac) = match data.find('.') {
+        Some(i) => (&data[..i], &data[i + 1..]),
+        None => (data, ""),
+    };
+    if frac.len() > decimals as usize || !frac.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
     }
 
-    buf.append(&mut data_buf);
-    Ok(())
+    let digits = format!("{}{:0<width$}", int, frac, width = decimals as usize);
+    BigInt::from_str(&digits).map_err(|_| invalid())
+}
+
+fn left_pad(data: &[u8], fill: u8) -> Vec<u8> {
+    let mut out = vec![fill; 32 - data.len()];
+    out.extend_from_slice(data);
+    out
+}
+
+fn right_pad(data: &[u8]) -> Vec<u8> {
+    let mut out = data.to_vec();
+    out.resize(data.len().div_ceil(32) * 32, 0);
+    out
+}
+
+fn encode_word(data: &[u8], fill: u8, value: &str) -> Result<Vec<u8>, ABIError> {
+    if data.len() > 32 {
+        return Err(ABIError::InvalidValue(String::from(value)));
+    }
+    Ok(left_pad(data, fill))
+}
+
+fn encode_usize(n: usize) -> Vec<u8> {
+    left_pad(&(n as u64).to_be_bytes(), 0)
+}
+
+fn encode_dynamic_bytes(data: &[u8]) -> Vec<u8> {
+    let mut out = encode_usize(data.len());
+    out.extend(right_pad(data));
+    out
+}
+
+/// Encodes values as a tuple: the heads of every value followed by the
+/// tails of the dynamic ones.
+fn encode_sequence(fields: &[&ABIField], values: &[ABIValue]) -> Result<Vec<u8>, ABIError> {
+    let mut heads: Vec<Vec<u8>> = Vec::with_capacity(fields.len());
+    let mut tails: Vec<Vec<u8>> = Vec::new();
+    let head_len: usize = fields.iter().map(|f| f.head_size()).sum();
+    let mut tail_len = 0;
+
+    for (field, value) in fields.iter().zip(values) {
+        let enc = field.encode(value)?;
+        if field.is_dynamic() {
+            heads.push(encode_usize(head_len + tail_len));
+            tail_len += enc.len();
+            tails.push(enc);
+        } else {
+            heads.push(enc);
+        }
+    }
+
+    heads.extend(tails);
+    Ok(heads.concat())
+}
+
+fn decode_sequence(fields: &[&ABIField], data: &[u8], base: usize) -> Result<Vec<ABIValue>, ABIError> {
+    let mut values = Vec::with_capacity(fields.len());
+    let mut pos = base;
+    for field in fields {
+        values.push(field.decode(data, base, pos)?);
+        pos += field.head_size();
+    }
+
+    Ok(values)
+}
+
+const KNOWN_SIGNATURES: &str = include_str!("signatures.txt");
+const OPENCHAIN_LOOKUP_URL: &str = "https://api.openchain.xyz/signature-database/v1/lookup?filter=true";
+const FOURBYTE_LOOKUP_URL: &str = "https://www.4byte.directory/api/v1";
+
+/// Keywords that may follow the parameters of a human-readable fragment.
+const FRAGMENT_MODIFIERS: &[&str] = &["view", "pure", "payable", "nonpayable", "external", "public", "anonymous", "returns"];
+
+/// Keywords that may appear between a parameter's type and its name.
+const PARAM_MODIFIERS: &[&str] = &["indexed", "memory", "calldata", "storage", "payable"];
+
+const AGGREGATE3_SIGNATURE: &str = "aggregate3((address,bool,bytes)[])";
+const AGGREGATE3_RESULT_TYPE: &str = "(bool,bytes)[]";
+
+/// Functions whose last argument is a batch of calls, either as bare calldata
+/// or as structs ending in the calldata.
+const MULTICALL_FUNCTIONS: &[&str] = &["aggregate", "tryAggregate", "blockAndAggregate", "tryBlockAndAggregate", "aggregate3", "aggregate3Value", "multicall"];
+
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The panic codes emitted by the Solidity compiler.
+const PANIC_CODES: &[(u64, &str)] = &[
+    (0x00, "generic compiler-inserted panic"),
+    (0x01, "assertion failed"),
+    (0x11, "arithmetic overflow or underflow"),
+    (0x12, "division or modulo by zero"),
+    (0x21, "invalid enum conversion"),
+    (0x22, "incorrectly encoded storage byte array"),
+    (0x31, "pop on an empty array"),
+    (0x32, "array index out of bounds"),
+    (0x41, "out of memory"),
+    (0x51, "call to a zero-initialized function"),
+];
+
+/// A function signature such as `transfer(address,uint256)`. Event and
+/// error signatures share the same form. Human-readable fragments such as
+/// `function balanceOf(address owner) view returns (uint256)` are also
+/// accepted; modifiers are discarded.
+pub struct FunctionSignature {
+    pub name: String,
+    inputs: Vec<ABIField>,
+    /// The parameter names, empty where the signature doesn't provide them.
+    names: Vec<String>,
+    /// Which parameters of an event are indexed.
+    indexed: Vec<bool>,
+    /// The function's return types, if known.
+    outputs: Vec<ABIField>,
+}
+
+impl FunctionSignature {
+    /// Builds a signature from a function entry in an ABI JSON file.
+    fn from_json(entry: &Value) -> Result<FunctionSignature, ABIError> {
+        let name = match (entry["name"].as_str(), entry["type"].as_str()) {
+            (Some(name), _) => name,
+            (None, Some("constructor")) => "constructor",
+            _ => return Err(ABIError::InvalidABIJson(String::from("function is missing a name"))),
+        };
+        let params = entry["inputs"].as_array().map(|p| p.as_slice()).unwrap_or(&[]);
+        let inputs = params.iter()
+            .map(|p| json_param_type(p).and_then(|t| ABIField::from_str(&t)))
+            .collect::<Result<Vec<ABIField>, ABIError>>()?;
+        let names = params.iter().map(|p| String::from(p["name"].as_str().unwrap_or(""))).collect();
+        let indexed = params.iter().map(|p| p["indexed"].as_bool().unwrap_or(false)).collect();
+        let outputs = entry["outputs"].as_array().map(|p| p.as_slice()).unwrap_or(&[]).iter()
+            .map(|p| json_param_type(p).and_then(|t| ABIField::from_str(&t)))
+            .collect::<Result<Vec<ABIField>, ABIError>>()?;
+        Ok(FunctionSignature { name: String::from(name), inputs, names, indexed, outputs })
+    }
+
+    /// Encodes calldata from a comma-separated list of argument values.
+    pub fn encode_call(&self, args: &str) -> Result<Vec<u8>, ABIError> {
+        let mut out = self.selector().to_vec();
+        out.extend(self.encode_args(args)?);
+        Ok(out)
+    }
+
+    /// Encodes a comma-separated list of argument values without a selector,
+    /// as appended to creation bytecode for constructors.
+    pub fn encode_args(&self, args: &str) -> Result<Vec<u8>, ABIError> {
+        let args = if args.trim().is_empty() { Vec::new() } else { split_top_level(args, ',') };
+        if args.len() != self.inputs.len() {
+            return Err(ABIError::TupleLengthMismatch(self.inputs.len(), args.len()));
+        }
+
+        let values = self.inputs.iter().zip(args)
+            .map(|(f, arg)| f.parse_value(arg.trim()))
+            .collect::<Result<Vec<ABIValue>, ABIError>>()?;
+        let fields: Vec<&ABIField> = self.inputs.iter().collect();
+        encode_sequence(&fields, &values)
+    }
+
+    /// Encodes calldata from a JSON array of argument values, or a JSON object
+    /// keyed by parameter name.
+    pub fn encode_json_call(&self, args: &Value) -> Result<Vec<u8>, ABIError> {
+        let args: Vec<&Value> = match args {
+            Value::Array(items) => items.iter().collect(),
+            Value::Object(map) => {
+                if self.names.iter().any(|n| n.is_empty()) {
+                    return Err(ABIError::InvalidJsonInput(format!("{} has unnamed parameters; pass its arguments as an array", self)));
+                }
+                if let Some(key) = map.keys().find(|k| !self.names.contains(k)) {
+                    return Err(ABIError::InvalidJsonInput(format!("{} has no parameter named {}", self, key)));
+                }
+                self.names.iter()
+                    .map(|n| map.get(n).ok_or_else(|| ABIError::InvalidJsonInput(format!("missing argument {}", n))))
+                    .collect::<Result<Vec<&Value>, ABIError>>()?
+            }
+            _ => return Err(ABIError::InvalidJsonInput(String::from("expected an array or object of arguments"))),
+        };
+        if args.len() != self.inputs.len() {
+            return Err(ABIError::TupleLengthMismatch(self.inputs.len(), args.len()));
+        }
+
+        let values = self.inputs.iter().zip(args)
+            .map(|(f, arg)| f.parse_json_value(arg))
+            .collect::<Result<Vec<ABIValue>, ABIError>>()?;
+        let fields: Vec<&ABIField> = self.inputs.iter().collect();
+        let mut out = self.selector().to_vec();
+        out.extend(encode_sequence(&fields, &values)?);
+        Ok(out)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.to_string().as_bytes());
+        let mut out: [u8; 4] = [0; 4];
+        out.copy_from_slice(&hash[..4]);
+        out
+    }
+
+    /// The event topic, i.e. the full keccak256 hash of the canonical signature.
+    pub fn topic(&self) -> [u8; 32] {
+        keccak256(self.to_string().as_bytes())
+    }
+
+    /// Decodes a log emitted by this event into its parameters in declaration
+    /// order. Indexed dynamic parameters are only available as their hash.
+    pub fn decode_log(&self, topics: &[Vec<u8>], data: &[u8]) -> Result<Vec<ABIValue>, ABIError> {
+        match topics.first() {
+            Some(t) if t[..] == self.topic() => {}
+            Some(t) => return Err(ABIError::SelectorMismatch(encode_hex(&self.topic()), encode_hex(t))),
+            None => return Err(ABIError::InvalidData(String::from("logs without topics cannot be decoded"))),
+        }
+        let indexed = self.indexed_params(topics.len() - 1);
+        let indexed_count = indexed.iter().filter(|i| **i).count();
+        if indexed_count != topics.len() - 1 {
+            return Err(ABIError::InvalidData(format!("expected {} indexed topics but got {}", indexed_count, topics.len() - 1)));
+        }
+
+        let data_fields: Vec<&ABIField> = self.inputs.iter().zip(&indexed).filter(|(_, i)| !**i).map(|(f, _)| f).collect();
+        let mut data_values = decode_sequence(&data_fields, data, 0)?.into_iter();
+        let mut topic_values = topics[1..].iter();
+        self.inputs.iter().zip(indexed).map(|(field, is_indexed)| {
+            if !is_indexed {
+                return data_values.next().ok_or_else(|| ABIError::InvalidData(String::from("missing log data")));
+            }
+            let topic = topic_values.next().ok_or_else(|| ABIError::InvalidData(String::from("missing topic")))?;
+            if field.is_dynamic() || field.is_composite() {
+                Ok(ABIValue::Bytes(topic.clone()))
+            } else {
+                field.decode(topic, 0, 0)
+            }
+        }).collect()
+    }
+
+    /// Decodes return data using the function's output types.
+    pub fn decode_return(&self, data: &[u8]) -> Result<Vec<ABIValue>, ABIError> {
+        let fields: Vec<&ABIField> = self.outputs.iter().collect();
+        decode_sequence(&fields, data, 0)
+    }
+
+    /// Which parameters are indexed. Signatures without `indexed` markers are
+    /// assumed to index their first `topic_count` parameters.
+    fn indexed_params(&self, topic_count: usize) -> Vec<bool> {
+        if self.indexed.iter().any(|i| *i) {
+            self.indexed.clone()
+        } else {
+            (0..self.inputs.len()).map(|i| i < topic_count).collect()
+        }
+    }
+
+    /// Decodes calldata, verifying that it starts with this function's selector.
+    pub fn decode_call(&self, data: &[u8]) -> Result<Vec<ABIValue>, ABIError> {
+        if data.len() < 4 {
+            return Err(ABIError::InvalidData(String::from("calldata is shorter than a selector")));
+        }
+        if data[..4] != self.selector() {
+            return Err(ABIError::SelectorMismatch(encode_hex(&self.selector()), encode_hex(&data[..4])));
+        }
+
+        let fields: Vec<&ABIField> = self.inputs.iter().collect();
+        decode_sequence(&fields, &data[4..], 0)
+    }
+}
+
+impl fmt::Display for FunctionSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inputs: Vec<String> = self.inputs.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}({})", self.name, inputs.join(","))
+    }
+}
+
+impl FromStr for FunctionSignature {
+    type Err = ABIError;
+
+    fn from_str(s: &str) -> Result<FunctionSignature, ABIError> {
+        let err = || ABIError::InvalidSignature(String::from(s));
+        let (kind, sig) = split_fragment_kind(s.trim());
+        let open = sig.find('(').ok_or_else(err)?;
+        let name = if kind == "constructor" { kind } else { sig[..open].trim() };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$') {
+            return Err(err());
+        }
+
+        let rest = &sig[open..];
+        let close = matching_paren(rest).ok_or_else(err)?;
+        let (inputs, names, indexed) = parse_params(&rest[1..close])?;
+        let outputs = match parse_fragment_suffix(&rest[close + 1..]).ok_or_else(err)? {
+            Some(outputs) => parse_params(outputs)?.0,
+            None => Vec::new(),
+        };
+
+        Ok(FunctionSignature { name: String::from(name), inputs, names, indexed, outputs })
+    }
+}
+
+/// Splits the leading `function`, `event`, `error`, or `constructor` keyword
+/// off a fragment. Fragments without one are treated as functions.
+fn split_fragment_kind(s: &str) -> (&str, &str) {
+    for kind in &["function", "event", "error"] {
+        if let Some(rest) = s.strip_prefix(kind) {
+            if rest.starts_with(char::is_whitespace) {
+                return (kind, rest.trim_start());
+            }
+        }
+    }
+    if let Some(rest) = s.strip_prefix("constructor") {
+        if rest.is_empty() || rest.starts_with(|c: char| c == '(' || c.is_whitespace()) {
+            return ("constructor", rest);
+        }
+    }
+    ("function", s)
+}
+
+/// Parses the text after a fragment's parameters, which may hold modifiers and
+/// parenthesized return types, as in `view returns (uint256)` or `(uint256)`.
+/// Returns the return types' inner text, or `None` if the suffix is invalid.
+fn parse_fragment_suffix(s: &str) -> Option<Option<&str>> {
+    let mut outputs = None;
+    let mut rest = s.trim();
+    while !rest.is_empty() {
+        if rest.starts_with('(') {
+            let close = matching_paren(rest)?;
+            if outputs.replace(&rest[1..close]).is_some() {
+                return None;
+            }
+            rest = rest[close + 1..].trim_start();
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '(').unwrap_or(rest.len());
+            if !FRAGMENT_MODIFIERS.contains(&&rest[..end]) {
+                return None;
+            }
+            rest = rest[end..].trim_start();
+        }
+    }
+    Some(outputs)
+}
+
+/// Splits a human-readable parameter such as `address indexed owner` or
+/// `tuple(uint256 a, bool b)[] items` into its type, name, and whether it is indexed.
+fn split_param(param: &str) -> Result<(String, String, bool), ABIError> {
+    let p = param.trim();
+    let p = p.strip_prefix("tuple").filter(|r| r.starts_with('(')).unwrap_or(p);
+    let (ty, rest) = if p.starts_with('(') {
+        let close = matching_paren(p).ok_or_else(|| ABIError::InvalidFieldType(String::from(param)))?;
+        let components = split_top_level(&p[1..close], ',').into_iter()
+            .map(|c| split_param(c).map(|(ty, _, _)| ty))
+            .collect::<Result<Vec<String>, ABIError>>()?;
+        let rest = &p[close + 1..];
+        let suffix_len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        (format!("({}){}", components.join(","), &rest[..suffix_len]), &rest[suffix_len..])
+    } else {
+        let len = p.find(char::is_whitespace).unwrap_or(p.len());
+        (String::from(&p[..len]), &p[len..])
+    };
+
+    let name = rest.split_whitespace().rfind(|t| !PARAM_MODIFIERS.contains(t)).unwrap_or("");
+    let indexed = rest.split_whitespace().any(|t| t == "indexed");
+    Ok((ty, String::from(name), indexed))
+}
+
+/// Parameter types, names, and indexed flags, as parsed from a fragment.
+type Params = (Vec<ABIField>, Vec<String>, Vec<bool>);
+
+fn parse_params(params: &str) -> Result<Params, ABIError> {
+    let mut fields = Vec::new();
+    let mut names = Vec::new();
+    let mut indexed = Vec::new();
+    if params.trim().is_empty() {
+        return Ok((fields, names, indexed));
+    }
+
+    for param in split_top_level(params, ',') {
+        let (ty, name, is_indexed) = split_param(param)?;
+        fields.push(ABIField::from_str(&ty)?);
+        names.push(name);
+        indexed.push(is_indexed);
+    }
+    Ok((fields, names, indexed))
+}
+
+/// Returns the index of the parenthesis closing the one that opens `s`.
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Converts an ABI JSON parameter into its canonical type, expanding tuple
+/// components into `(t1,t2)` form.
+fn json_param_type(param: &Value) -> Result<String, ABIError> {
+    let ty = param["type"].as_str()
+        .ok_or_else(|| ABIError::InvalidABIJson(String::from("parameter is missing a type")))?;
+    if !ty.starts_with("tuple") {
+        return Ok(String::from(ty));
+    }
+
+    let components = param["components"].as_array()
+        .ok_or_else(|| ABIError::InvalidABIJson(String::from("tuple is missing its components")))?
+        .iter().map(json_param_type).collect::<Result<Vec<String>, ABIError>>()?;
+    Ok(format!("({}){}", components.join(","), &ty["tuple".len()..]))
+}
+
+/// Loads the entries of the given kind, e.g. `function` or `error`, from an ABI.
+/// `abi` is either a path to a JSON ABI, or human-readable fragments given
+/// inline and separated by semicolons. JSON ABIs may be bare arrays or compiler
+/// artifacts with an `abi` field (Foundry, Hardhat, Truffle), and their entries
+/// may be objects or human-readable fragment strings.
+pub fn load_abi_entries(abi: &str, kind: &str) -> util::Res<Vec<FunctionSignature>> {
+    let text = if abi.contains('(') && !Path::new(abi).is_file() {
+        String::from(abi)
+    } else {
+        fs::read_to_string(abi)?
+    };
+
+    let json: Value = if text.trim_start().starts_with(['[', '{']) {
+        serde_json::from_str(&text)?
+    } else {
+        text.split([';', '\n'])
+            .filter(|f| !f.trim().is_empty())
+            .map(|f| Value::String(String::from(f)))
+            .collect()
+    };
+    let entries = match json.get("abi") {
+        Some(abi) => abi.as_array(),
+        None => json.as_array(),
+    }.ok_or_else(|| ABIError::InvalidABIJson(String::from("expected an array of ABI entries")))?;
+
+    let funcs = entries.iter()
+        .filter(|e| abi_entry_kind(e) == kind)
+        .map(|e| match e.as_str() {
+            Some(fragment) => FunctionSignature::from_str(fragment),
+            None => FunctionSignature::from_json(e),
+        })
+        .collect::<Result<Vec<FunctionSignature>, ABIError>>()?;
+    Ok(funcs)
+}
+
+fn abi_entry_kind(entry: &Value) -> &str {
+    match entry.as_str() {
+        Some(fragment) => split_fragment_kind(fragment.trim()).0,
+        None => entry["type"].as_str().unwrap_or("function"),
+    }
+}
+
+/// Finds a function in an ABI by name or full signature. Overloads are
+/// narrowed by `arg_count` when it is provided.
+pub fn find_function<'a>(funcs: &'a [FunctionSignature], query: &str, arg_count: Option<usize>) -> Result<&'a FunctionSignature, ABIError> {
+    let mut matches: Vec<&FunctionSignature> = if query.contains('(') {
+        let canonical = FunctionSignature::from_str(query)?.to_string();
+        funcs.iter().filter(|f| f.to_string() == canonical).collect()
+    } else {
+        funcs.iter().filter(|f| f.name == query).collect()
+    };
+    if matches.len() > 1 {
+        if let Some(n) = arg_count {
+            matches.retain(|f| f.inputs.len() == n);
+        }
+    }
+
+    match matches.len() {
+        0 => Err(ABIError::UnknownFunction(String::from(query))),
+        1 => Ok(matches.remove(0)),
+        _ => Err(ABIError::AmbiguousFunction(String::from(query), matches.iter().map(|f| f.to_string()).collect())),
+    }
+}
+
+/// Finds the bundled signatures whose selector matches `selector`.
+pub fn lookup_known_selector(selector: &[u8]) -> Vec<FunctionSignature> {
+    KNOWN_SIGNATURES.lines()
+        .filter_map(|line| FunctionSignature::from_str(line).ok())
+        .filter(|sig| sig.selector()[..] == *selector)
+        .collect()
+}
+
+/// Queries openchain.xyz, then 4byte.directory, for signatures matching a
+/// function selector or, when `kind` is `event`, an event topic.
+fn lookup_remote_signature(kind: &str, hash: &str) -> util::Res<Vec<String>> {
+    let res = util::http_get_json(&format!("{}&{}={}", OPENCHAIN_LOOKUP_URL, kind, hash))?;
+    let mut sigs: Vec<String> = res["result"][kind][hash].as_array()
+        .map(|items| items.iter().filter_map(|i| i["name"].as_str()).map(String::from).collect())
+        .unwrap_or_default();
+    if sigs.is_empty() {
+        let endpoint = if kind == "event" { "event-signatures" } else { "signatures" };
+        let res = util::http_get_json(&format!("{}/{}/?hex_signature={}", FOURBYTE_LOOKUP_URL, endpoint, hash))?;
+        sigs = res["results"].as_array()
+            .map(|items| items.iter().filter_map(|i| i["text_signature"].as_str()).map(String::from).collect())
+            .unwrap_or_default();
+    }
+    Ok(sigs)
+}
+
+/// Splits `s` on `sep`, ignoring separators nested inside brackets, parentheses,
+/// or double quotes, and separators escaped with a backslash.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '[' | '(' => depth += 1,
+            ']' | ')' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Strips surrounding double quotes from a scalar value and resolves
+/// backslash escapes, so that `"a, b"` and `a\, b` both yield `a, b`.
+fn unquote(s: &str) -> String {
+    let trimmed = s.trim();
+    let inner = if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+        &trimmed[1..trimmed.len() - 1]
+    } else {
+        s
+    };
+
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Splits a grouped value such as `[1,2,3]` into its trimmed elements.
+fn split_group(s: &str, open: char, close: char) -> Result<Vec<&str>, ABIError> {
+    let s = s.trim();
+    if !s.starts_with(open) || !s.ends_with(close) || s.len() < 2 {
+        return Err(ABIError::InvalidValue(String::from(s)));
+    }
+
+    let inner = s[1..s.len() - 1].trim();
+    if inner.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(split_top_level(inner, ',').into_iter().map(|item| item.trim()).collect())
+}
+
+fn with_abi_args<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.arg(Arg::with_name("abi")
+            .long("abi")
+            .takes_value(true)
+            .requires("fn")
+            .help("A Solidity ABI JSON file or compiler artifact to look the function up in, or human-readable fragments separated by semicolons."))
+        .arg(Arg::with_name("fn")
+            .long("fn")
+            .takes_value(true)
+            .requires("abi")
+            .help("The function's name, or its full signature to pick between overloads."))
+}
+
+fn with_json_arg<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.arg(Arg::with_name("json")
+        .long("json")
+        .help("Reads the input as JSON: an array of {\"type\", \"value\"} objects, or with --abi an array of values or an object keyed by parameter name."))
 }
 
 pub fn make_abi_cmd<'a, 'b>() -> App<'a, 'b> {
-    let encode_cmd = SubCommand::with_name("encode-packed")
-        .arg(make_input_arg("the data to encode and its schema. If - is provided, will read from stdin"));
+    let encode_cmd = with_json_arg(with_abi_args(SubCommand::with_name("encode")))
+        .arg(make_input_arg("the data to encode and its schema, or only the argument values when --abi is given. Bytes values may be read from a file with bytes:@path. If - is provided, each line of stdin is encoded separately"))
+        .about("Encodes data using the standard ABI encoding, or calldata for a function in an ABI file");
+    let encode_packed_cmd = with_json_arg(SubCommand::with_name("encode-packed"))
+        .arg(make_input_arg("the data to encode and its schema. Bytes values may be read from a file with bytes:@path. If - is provided, each line of stdin is encoded separately"));
+    let decode_cmd = with_abi_args(SubCommand::with_name("decode"))
+        .arg(make_input_arg("the hex-encoded data to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("types")
+            .short("-t")
+            .required_unless("abi")
+            .conflicts_with("abi")
+            .takes_value(true)
+            .help("A comma-separated list of the encoded types, e.g. address,uint256,bytes"))
+        .about("Decodes ABI-encoded data, printing one value per line");
+    let decode_return_cmd = with_abi_args(SubCommand::with_name("decode-return"))
+        .arg(make_input_arg("the hex-encoded return data to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .takes_value(true)
+            .required_unless("abi")
+            .conflicts_with("abi")
+            .help("The function signature with its return types, e.g. balanceOf(address)(uint256)"))
+        .about("Decodes eth_call return data using a function's output types, printing one value per line");
+    let decode_packed_cmd = SubCommand::with_name("decode-packed")
+        .arg(make_input_arg("the hex-encoded packed data to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("types")
+            .short("-t")
+            .required(true)
+            .takes_value(true)
+            .help("A comma-separated list of the packed types, e.g. address,uint96. At most one may be dynamic."))
+        .about("Splits abi.encodePacked data back into its fields, printing one value per line");
+    let decode_call_cmd = SubCommand::with_name("decode-call")
+        .arg(make_input_arg("the hex-encoded calldata to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .takes_value(true)
+            .help("The function signature, e.g. transfer(address,uint256). If omitted, the selector is matched against known signatures."))
+        .about("Decodes calldata into its function signature and arguments");
+    let decode_log_cmd = SubCommand::with_name("decode-log")
+        .arg(make_input_arg("the hex-encoded log data. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("topic")
+            .short("-t")
+            .long("topic")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(true)
+            .help("A log topic, starting with topic0. Repeat for each topic."))
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .takes_value(true)
+            .conflicts_with("abi")
+            .help("The event signature, e.g. \"Transfer(address indexed from, address indexed to, uint256 value)\""))
+        .arg(Arg::with_name("abi")
+            .long("abi")
+            .takes_value(true)
+            .help("A Solidity ABI JSON file, compiler artifact, or human-readable fragments to find the event in."))
+        .about("Decodes an event log, printing its signature and one parameter per line. Without --sig or --abi, the signature is looked up on openchain.xyz");
+    let pretty_cmd = SubCommand::with_name("pretty")
+        .arg(make_input_arg("the hex-encoded calldata or ABI data to print. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .takes_value(true)
+            .help("The function signature, used to label each word with the parameter it encodes."))
+        .about("Prints calldata as its selector and 32-byte words with offsets");
+    let canonicalize_cmd = SubCommand::with_name("canonicalize")
+        .arg(Arg::with_name("sig")
+            .help("the signature or human-readable fragment to canonicalize")
+            .index(1)
+            .required(true))
+        .about("Prints the canonical form of a signature, as hashed for selectors and topics");
+    let event_topic_cmd = SubCommand::with_name("event-topic")
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .required(true)
+            .takes_value(true)
+            .help("The event signature, e.g. Transfer(address,address,uint256)"))
+        .about("Computes the topic0 hash of an event signature for use in log filters");
+    let encode_constructor_cmd = SubCommand::with_name("encode-constructor")
+        .arg(make_input_arg("the constructor arguments and their schema, or only the values when --abi is given. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("bytecode")
+            .short("-b")
+            .long("bytecode")
+            .takes_value(true)
+            .required(true)
+            .help("The hex-encoded creation bytecode, or a file containing it. Foundry and Hardhat artifacts are also accepted."))
+        .arg(Arg::with_name("abi")
+            .long("abi")
+            .takes_value(true)
+            .help("A Solidity ABI JSON file, compiler artifact, or human-readable fragment to take the constructor's types from."))
+        .about("Appends ABI-encoded constructor arguments to creation bytecode, producing deployment initcode");
+    let multicall_cmd = SubCommand::with_name("multicall")
+        .arg(Arg::with_name("call")
+            .short("-c")
+            .long("call")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .required(true)
+            .help("A call as <target>:<calldata>, or <target>:<signature>:<args>, e.g. 0x...:balanceOf(address):0x..."))
+        .arg(Arg::with_name("allow-failure")
+            .long("allow-failure")
+            .help("Lets individual calls revert without reverting the batch."))
+        .about("Encodes aggregate3 calldata for the Multicall3 contract at 0xcA11bde05977b3631167028862bE2a173976CA11");
+    let decode_multicall_cmd = SubCommand::with_name("decode-multicall")
+        .arg(make_input_arg("the hex-encoded aggregate3 return data. If - is provided, will read from stdin"))
+        .about("Decodes Multicall3 aggregate3 return data, printing each call's success and return data");
+    let storage_slot_cmd = SubCommand::with_name("storage-slot")
+        .arg(Arg::with_name("slot")
+            .short("-s")
+            .long("slot")
+            .takes_value(true)
+            .required_unless("eip1967")
+            .help("The slot of the variable as declared in the contract's storage layout."))
+        .arg(Arg::with_name("key")
+            .short("-k")
+            .long("key")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("A mapping key as type:value, e.g. address:0x.... Repeat for nested mappings."))
+        .arg(Arg::with_name("index")
+            .long("index")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("A dynamic array index. Keys and indexes are applied in the order given."))
+        .arg(Arg::with_name("element-slots")
+            .long("element-slots")
+            .takes_value(true)
+            .default_value("1")
+            .help("The number of slots each array element occupies."))
+        .arg(Arg::with_name("eip1967")
+            .long("eip1967")
+            .takes_value(true)
+            .possible_values(&["implementation", "admin", "beacon"])
+            .conflicts_with_all(&["slot", "key", "index"])
+            .help("Prints an EIP-1967 proxy slot instead."))
+        .about("Computes the storage slot of a mapping entry, array element, or EIP-1967 proxy field for eth_getStorageAt");
+    let decode_storage_cmd = SubCommand::with_name("decode-storage")
+        .arg(make_input_arg("the 32-byte storage word to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("types")
+            .short("-t")
+            .required(true)
+            .takes_value(true)
+            .help("The variables packed into the slot in declaration order, e.g. uint128,address. A type may be given as type@offset to use an explicit byte offset from solc's storage layout."))
+        .about("Splits a storage word into the packed variables it holds, printing one value per line");
+    let lookup_selector_cmd = SubCommand::with_name("lookup-selector")
+        .arg(Arg::with_name("selector")
+            .help("the 4-byte function selector to look up, e.g. 0xa9059cbb")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("offline")
+            .long("offline")
+            .help("Only searches the bundled list of common signatures."))
+        .about("Finds function signatures matching a selector via openchain.xyz and 4byte.directory");
+    let decode_revert_cmd = SubCommand::with_name("decode-revert")
+        .arg(make_input_arg("the hex-encoded revert data to decode. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("sig")
+            .short("-s")
+            .long("sig")
+            .takes_value(true)
+            .conflicts_with("abi")
+            .help("The signature of a custom error, e.g. InsufficientBalance(uint256,uint256)"))
+        .arg(Arg::with_name("abi")
+            .long("abi")
+            .takes_value(true)
+            .help("A Solidity ABI JSON file or compiler artifact whose custom errors to match against, or human-readable fragments separated by semicolons."))
+        .about("Explains revert data from Error(string), Panic(uint256), or a custom error");
 
     SubCommand::with_name("abi")
         .subcommand(encode_cmd)
+        .subcommand(encode_packed_cmd)
+        .subcommand(decode_cmd)
+        .subcommand(decode_return_cmd)
+        .subcommand(decode_packed_cmd)
+        .subcommand(decode_call_cmd)
+        .subcommand(decode_log_cmd)
+        .subcommand(pretty_cmd)
+        .subcommand(canonicalize_cmd)
+        .subcommand(event_topic_cmd)
+        .subcommand(encode_constructor_cmd)
+        .subcommand(multicall_cmd)
+        .subcommand(storage_slot_cmd)
+        .subcommand(decode_storage_cmd)
+        .subcommand(decode_multicall_cmd)
+        .subcommand(lookup_selector_cmd)
+        .subcommand(decode_revert_cmd)
         .about("Encode and decode data using Ethereum's ABI.")
 }
 
 pub fn execute_abi_cmd(matches: &ArgMatches) -> util::Res<String> {
     match matches.subcommand() {
-        ("encode-packed", Some(sub)) => execute_encode_packed_cmd(sub.value_of("input").unwrap()),
+        ("encode", Some(sub)) => match sub.value_of("abi") {
+            Some(abi) => execute_encode_fn_cmd(sub.value_of("input").unwrap(), abi, sub.value_of("fn").unwrap(), sub.is_present("json")),
+            None => execute_encode_cmd(sub.value_of("input").unwrap(), sub.is_present("json")),
+        },
+        ("encode-packed", Some(sub)) => execute_encode_packed_cmd(sub.value_of("input").unwrap(), sub.is_present("json")),
+        ("decode", Some(sub)) => match sub.value_of("abi") {
+            Some(abi) => execute_decode_fn_cmd(sub.value_of("input").unwrap(), abi, sub.value_of("fn").unwrap()),
+            None => execute_decode_cmd(sub.value_of("input").unwrap(), sub.value_of("types").unwrap()),
+        },
+        ("decode-return", Some(sub)) => execute_decode_return_cmd(sub.value_of("input").unwrap(), sub.value_of("sig"), sub.value_of("abi"), sub.value_of("fn")),
+        ("decode-packed", Some(sub)) => execute_decode_packed_cmd(sub.value_of("input").unwrap(), sub.value_of("types").unwrap()),
+        ("decode-call", Some(sub)) => execute_decode_call_cmd(sub.value_of("input").unwrap(), sub.value_of("sig")),
+        ("decode-log", Some(sub)) => execute_decode_log_cmd(sub.value_of("input").unwrap(), sub.values_of("topic").unwrap().collect(), sub.value_of("sig"), sub.value_of("abi")),
+        ("pretty", Some(sub)) => execute_pretty_cmd(sub.value_of("input").unwrap(), sub.value_of("sig")),
+        ("canonicalize", Some(sub)) => execute_canonicalize_cmd(sub.value_of("sig").unwrap()),
+        ("event-topic", Some(sub)) => execute_event_topic_cmd(sub.value_of("sig").unwrap()),
+        ("encode-constructor", Some(sub)) => execute_encode_constructor_cmd(sub.value_of("input").unwrap(), sub.value_of("bytecode").unwrap(), sub.value_of("abi")),
+        ("multicall", Some(sub)) => execute_multicall_cmd(sub.values_of("call").unwrap().collect(), sub.is_present("allow-failure")),
+        ("decode-multicall", Some(sub)) => execute_decode_multicall_cmd(sub.value_of("input").unwrap()),
+        ("storage-slot", Some(sub)) => execute_storage_slot_cmd(sub),
+        ("decode-storage", Some(sub)) => execute_decode_storage_cmd(sub.value_of("input").unwrap(), sub.value_of("types").unwrap()),
+        ("lookup-selector", Some(sub)) => execute_lookup_selector_cmd(sub.value_of("selector").unwrap(), sub.is_present("offline")),
+        ("decode-revert", Some(sub)) => execute_decode_revert_cmd(sub.value_of("input").unwrap(), sub.value_of("sig"), sub.value_of("abi")),
         (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into()),
     }
 }
 
-fn execute_encode_packed_cmd(input: &str) -> util::Res<String> {
-    let res = encode_abi_packed(input)?;
-    Ok(encode_hex(&res))
+/// Encodes the input, or when it is `-`, each line of stdin independently,
+/// producing one hex-encoded result per line.
+fn encode_lines<F: Fn(&str) -> util::Res<Vec<u8>>>(input: &str, encode: F) -> util::Res<String> {
+    if input != "-" {
+        return Ok(encode_hex(&encode(input)?));
+    }
+
+    let mut out = Vec::new();
+    for (i, line) in io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let res = encode(&line).map_err(|e| CmdError::InvalidInput(format!("line {}: {}", i + 1, e)))?;
+        out.push(encode_hex(&res));
+    }
+    Ok(out.join("\n"))
+}
+
+fn execute_encode_cmd(input: &str, json: bool) -> util::Res<String> {
+    encode_lines(input, |data| {
+        Ok(if json { encode_fields(parse_json_fields(data)?)? } else { encode_abi(data)? })
+    })
+}
+
+fn execute_encode_fn_cmd(input: &str, abi: &str, func: &str, json: bool) -> util::Res<String> {
+    let funcs = load_abi_entries(abi, "function")?;
+    encode_lines(input, |args| {
+        if json {
+            let args: Value = serde_json::from_str(args).map_err(|e| ABIError::InvalidJsonInput(e.to_string()))?;
+            let arg_count = args.as_array().map(|a| a.len()).or_else(|| args.as_object().map(|o| o.len()));
+            return Ok(find_function(&funcs, func, arg_count)?.encode_json_call(&args)?);
+        }
+
+        let arg_count = if args.trim().is_empty() { 0 } else { split_top_level(args, ',').len() };
+        Ok(find_function(&funcs, func, Some(arg_count))?.encode_call(args)?)
+    })
+}
+
+fn execute_encode_packed_cmd(input: &str, json: bool) -> util::Res<String> {
+    encode_lines(input, |data| {
+        Ok(if json { encode_fields_packed(parse_json_fields(data)?)? } else { encode_abi_packed(data)? })
+    })
+}
+
+fn execute_decode_cmd(input: &str, types: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let values = decode_abi(types, &data)?;
+    let lines: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    Ok(lines.join("\n"))
+}
+
+fn execute_decode_return_cmd(input: &str, sig: Option<&str>, abi: Option<&str>, func: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let func = match (sig, abi, func) {
+        (Some(s), _, _) => FunctionSignature::from_str(s)?,
+        (None, Some(abi), Some(func)) => {
+            let funcs = load_abi_entries(abi, "function")?;
+            return execute_decode_return(&data, find_function(&funcs, func, None)?);
+        }
+        _ => return Err(CmdError::InvalidArgument(String::from("a signature, or an ABI and function, is required")).into()),
+    };
+    execute_decode_return(&data, &func)
+}
+
+fn execute_decode_return(data: &[u8], func: &FunctionSignature) -> util::Res<String> {
+    let values = func.decode_return(data)?;
+    let lines: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    Ok(lines.join("\n"))
+}
+
+fn execute_decode_packed_cmd(input: &str, types: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let values = decode_abi_packed(types, &data)?;
+    let lines: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    Ok(lines.join("\n"))
+}
+
+fn execute_decode_fn_cmd(input: &str, abi: &str, func: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let funcs = load_abi_entries(abi, "function")?;
+    let values = find_function(&funcs, func, None)?.decode_call(&data)?;
+    let lines: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    Ok(lines.join("\n"))
+}
+
+fn execute_decode_call_cmd(input: &str, sig: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    if data.len() < 4 {
+        return Err(ABIError::InvalidData(String::from("calldata is shorter than a selector")).into());
+    }
+
+    let func = match sig {
+        Some(s) => FunctionSignature::from_str(s)?,
+        None => lookup_known_selector(&data[..4]).into_iter().next()
+            .ok_or_else(|| ABIError::UnknownSelector(encode_hex(&data[..4])))?,
+    };
+    Ok(describe_call(&func, &data, "", "")?.join("\n"))
+}
+
+/// Decodes calldata into lines of its signature followed by its arguments,
+/// recursing into the calls batched by Multicall-style functions.
+fn describe_call(func: &FunctionSignature, data: &[u8], indent: &str, arg_indent: &str) -> Result<Vec<String>, ABIError> {
+    let mut values = func.decode_call(data)?;
+    let batch = match values.last() {
+        Some(ABIValue::Array(_)) if MULTICALL_FUNCTIONS.contains(&func.name.as_str()) => values.pop(),
+        _ => None,
+    };
+
+    let mut lines = vec![format!("{}{}", indent, func)];
+    lines.extend(values.iter().map(|v| format!("{}{}", arg_indent, v)));
+    if let Some(ABIValue::Array(calls)) = batch {
+        for (i, call) in calls.iter().enumerate() {
+            let (context, calldata) = match call {
+                ABIValue::Bytes(b) => (Vec::new(), b),
+                ABIValue::Tuple(items) => match items.split_last() {
+                    Some((ABIValue::Bytes(b), rest)) => (rest.iter().map(|v| v.to_string()).collect(), b),
+                    _ => return Err(ABIError::InvalidData(format!("{} is not a batched call", call))),
+                },
+                _ => return Err(ABIError::InvalidData(format!("{} is not a batched call", call))),
+            };
+            lines.push(format!("{}[{}] {}", arg_indent, i, context.join(", ")).trim_end().to_string());
+            lines.extend(describe_inner_call(calldata, &format!("{}  ", arg_indent)));
+        }
+    }
+    Ok(lines)
+}
+
+/// Describes a batched call, falling back to its raw calldata when no bundled
+/// signature decodes it.
+fn describe_inner_call(data: &[u8], indent: &str) -> Vec<String> {
+    let arg_indent = format!("{}  ", indent);
+    if data.len() >= 4 {
+        for func in lookup_known_selector(&data[..4]) {
+            if let Ok(lines) = describe_call(&func, data, indent, &arg_indent) {
+                return lines;
+            }
+        }
+    }
+
+    vec![format!("{}unknown call", indent), format!("{}{}", arg_indent, encode_hex(data))]
+}
+
+fn execute_decode_log_cmd(input: &str, topics: Vec<&str>, sig: Option<&str>, abi: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let topics = topics.iter().map(|t| match decode_hex(t) {
+        Ok(topic) if topic.len() == 32 => Ok(topic),
+        _ => Err(CmdError::InvalidArgument(format!("{} is not a 32-byte topic", t))),
+    }).collect::<Result<Vec<Vec<u8>>, CmdError>>()?;
+    let topic0 = encode_hex(&topics[0]);
+
+    let (events, guessed) = match (sig, abi) {
+        (Some(s), _) => {
+            let event = FunctionSignature::from_str(s)?;
+            let values = event.decode_log(&topics, &data)?;
+            return Ok(describe_log(&event, &values, topics.len() - 1, false));
+        }
+        (None, Some(abi)) => (load_abi_entries(abi, "event")?, false),
+        (None, None) => {
+            let events = lookup_remote_signature("event", &topic0)?.iter()
+                .filter_map(|s| FunctionSignature::from_str(s).ok())
+                .collect();
+            (events, true)
+        }
+    };
+
+    events.iter()
+        .filter(|e| e.topic()[..] == topics[0][..])
+        .find_map(|e| e.decode_log(&topics, &data).ok().map(|values| describe_log(e, &values, topics.len() - 1, guessed)))
+        .ok_or_else(|| ABIError::UnknownSelector(topic0).into())
+}
+
+fn describe_log(event: &FunctionSignature, values: &[ABIValue], topic_count: usize, guessed: bool) -> String {
+    let mut lines = vec![if guessed {
+        format!("{} (guessed from openchain.xyz; indexed parameters are assumed to come first)", event)
+    } else {
+        event.to_string()
+    }];
+    let indexed = event.indexed_params(topic_count);
+    for ((field, value), is_indexed) in event.inputs.iter().zip(values).zip(indexed) {
+        if is_indexed && (field.is_dynamic() || field.is_composite()) {
+            lines.push(format!("{} (keccak256 of the indexed value)", value));
+        } else {
+            lines.push(value.labeled());
+        }
+    }
+    lines.join("\n")
+}
+
+fn execute_pretty_cmd(input: &str, sig: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let func = sig.map(FunctionSignature::from_str).transpose()?;
+    let mut lines = Vec::new();
+
+    // Calldata is a selector followed by whole words; anything else is plain ABI data.
+    let args = if func.is_some() || data.len() % 32 == 4 {
+        if data.len() < 4 {
+            return Err(ABIError::InvalidData(String::from("calldata is shorter than a selector")).into());
+        }
+        match &func {
+            Some(f) if data[..4] != f.selector() => {
+                return Err(ABIError::SelectorMismatch(encode_hex(&f.selector()), encode_hex(&data[..4])).into());
+            }
+            Some(f) => lines.push(format!("selector: {} {}", encode_hex(&data[..4]), f)),
+            None => lines.push(format!("selector: {}", encode_hex(&data[..4]))),
+        }
+        &data[4..]
+    } else {
+        &data[..]
+    };
+
+    let mut labels = vec![String::new(); args.len().div_ceil(32)];
+    if let Some(f) = &func {
+        let mut pos = 0;
+        for (i, field) in f.inputs.iter().enumerate() {
+            let name = match f.names.get(i) {
+                Some(n) if !n.is_empty() => n.clone(),
+                _ => format!("arg{}", i),
+            };
+            field.label_words(args, 0, pos, &name, &mut labels);
+            pos += field.head_size();
+        }
+    }
+
+    for (i, (word, label)) in args.chunks(32).zip(labels).enumerate() {
+        lines.push(format!("0x{:03x}: {} {}", i * 32, hex::encode(word), label).trim_end().to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+fn execute_canonicalize_cmd(sig: &str) -> util::Res<String> {
+    Ok(FunctionSignature::from_str(sig)?.to_string())
+}
+
+fn execute_event_topic_cmd(sig: &str) -> util::Res<String> {
+    let event = FunctionSignature::from_str(sig)?;
+    Ok(encode_hex(&event.topic()))
+}
+
+fn execute_encode_constructor_cmd(input: &str, bytecode: &str, abi: Option<&str>) -> util::Res<String> {
+    let mut out = read_bytecode(bytecode)?;
+    let args = String::from_utf8(util::read_raw_input(input)?)?;
+    match abi {
+        Some(abi) => {
+            let ctor = load_abi_entries(abi, "constructor")?.into_iter().next()
+                .unwrap_or(FunctionSignature { name: String::from("constructor"), inputs: Vec::new(), names: Vec::new(), indexed: Vec::new(), outputs: Vec::new() });
+            out.extend(ctor.encode_args(&args)?);
+        }
+        None if args.trim().is_empty() => {}
+        None => out.extend(encode_abi(&args)?),
+    }
+    Ok(encode_hex(&out))
+}
+
+/// Reads creation bytecode given as hex, or from a file containing hex or a
+/// Foundry (`bytecode.object`) or Hardhat (`bytecode`) artifact.
+fn read_bytecode(bytecode: &str) -> util::Res<Vec<u8>> {
+    if bytecode.starts_with("0x") {
+        return Ok(decode_hex(bytecode)?);
+    }
+
+    let text = fs::read_to_string(bytecode)?;
+    let text = text.trim();
+    if !text.starts_with('{') {
+        return Ok(decode_hex(text)?);
+    }
+    let json: Value = serde_json::from_str(text)?;
+    let hex = json["bytecode"]["object"].as_str()
+        .or_else(|| json["bytecode"].as_str())
+        .ok_or_else(|| ABIError::InvalidABIJson(String::from("artifact has no bytecode")))?;
+    Ok(decode_hex(hex)?)
+}
+
+fn execute_multicall_cmd(calls: Vec<&str>, allow_failure: bool) -> util::Res<String> {
+    let calls = calls.into_iter()
+        .map(|c| parse_multicall_call(c, allow_failure))
+        .collect::<Result<Vec<ABIValue>, ABIError>>()?;
+    let func = FunctionSignature::from_str(AGGREGATE3_SIGNATURE)?;
+    let fields: Vec<&ABIField> = func.inputs.iter().collect();
+
+    let mut out = func.selector().to_vec();
+    out.extend(encode_sequence(&fields, &[ABIValue::Array(calls)])?);
+    Ok(encode_hex(&out))
+}
+
+/// Parses a `<target>:<calldata>` or `<target>:<signature>:<args>` call into
+/// a Multicall3 `Call3` struct.
+fn parse_multicall_call(call: &str, allow_failure: bool) -> Result<ABIValue, ABIError> {
+    let invalid = || ABIError::InvalidValue(String::from(call));
+    let (target, rest) = call.split_once(':').ok_or_else(invalid)?;
+    let target = parse_address(target.trim())?;
+    let rest = rest.trim();
+
+    let calldata = if rest.starts_with("0x") {
+        decode_hex(rest)?
+    } else {
+        let open = rest.find('(').ok_or_else(invalid)?;
+        let close = open + matching_paren(&rest[open..]).ok_or_else(invalid)?;
+        let args = match &rest[close + 1..] {
+            "" => "",
+            args => args.strip_prefix(':').ok_or_else(invalid)?,
+        };
+        FunctionSignature::from_str(&rest[..=close])?.encode_call(args)?
+    };
+    Ok(ABIValue::Tuple(vec![target, ABIValue::Boolean(allow_failure), ABIValue::Bytes(calldata)]))
+}
+
+fn execute_decode_multicall_cmd(input: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let field = ABIField::from_str(AGGREGATE3_RESULT_TYPE)?;
+    let results = match decode_sequence(&[&field], &data, 0)?.remove(0) {
+        ABIValue::Array(results) => results,
+        _ => return Err(ABIError::InvalidData(String::from("expected an array of results")).into()),
+    };
+
+    let lines: Vec<String> = results.iter().enumerate().map(|(i, r)| match r {
+        ABIValue::Tuple(items) => format!("{}: success={} {}", i, items[0], items[1]),
+        _ => format!("{}: {}", i, r),
+    }).collect();
+    Ok(lines.join("\n"))
+}
+
+enum SlotStep<'a> {
+    Key(&'a str),
+    Index(&'a str),
+}
+
+fn execute_storage_slot_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let max = BigUint::from(1u8) << 256;
+    if let Some(kind) = matches.value_of("eip1967") {
+        let hash = BigUint::from_bytes_be(&keccak256(format!("eip1967.proxy.{}", kind).as_bytes()));
+        return Ok(encode_hex(&left_pad(&(hash - 1u8).to_bytes_be(), 0)));
+    }
+
+    let parse_slot = |s: &str| parse_integer(s)?.to_biguint()
+        .filter(|n| *n < max)
+        .ok_or_else(|| ABIError::InvalidValue(String::from(s)));
+    let mut slot = parse_slot(matches.value_of("slot").unwrap())?;
+    let element_slots = parse_slot(matches.value_of("element-slots").unwrap())?;
+
+    let mut steps: Vec<(usize, SlotStep)> = Vec::new();
+    if let (Some(idx), Some(keys)) = (matches.indices_of("key"), matches.values_of("key")) {
+        steps.extend(idx.zip(keys.map(SlotStep::Key)));
+    }
+    if let (Some(idx), Some(indexes)) = (matches.indices_of("index"), matches.values_of("index")) {
+        steps.extend(idx.zip(indexes.map(SlotStep::Index)));
+    }
+    steps.sort_by_key(|(i, _)| *i);
+
+    for (_, step) in steps {
+        let slot_word = left_pad(&slot.to_bytes_be(), 0);
+        slot = match step {
+            SlotStep::Key(key) => {
+                let (field, value) = parse_field(key)?;
+                // Dynamic keys are hashed unpadded; value types use their ABI word.
+                let mut preimage = match (&field, &value) {
+                    (ABIField::String, ABIValue::String(s)) => s.as_bytes().to_vec(),
+                    (ABIField::Bytes, ABIValue::Bytes(b)) => b.clone(),
+                    _ if field.is_dynamic() || field.is_composite() => return Err(ABIError::InvalidFieldType(field.to_string()).into()),
+                    _ => field.encode(&value)?,
+                };
+                preimage.extend(slot_word);
+                BigUint::from_bytes_be(&keccak256(&preimage))
+            }
+            SlotStep::Index(index) => {
+                let base = BigUint::from_bytes_be(&keccak256(&slot_word));
+                (base + parse_slot(index)? * &element_slots) % &max
+            }
+        };
+    }
+
+    Ok(encode_hex(&left_pad(&slot.to_bytes_be(), 0)))
+}
+
+/// Decodes variables packed into a storage word. Solidity packs variables
+/// starting from the word's lowest-order byte, so the offset counts from the right.
+fn execute_decode_storage_cmd(input: &str, types: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    if data.len() > 32 {
+        return Err(ABIError::InvalidData(format!("storage words are 32 bytes but got {}", data.len())).into());
+    }
+    let word = left_pad(&data, 0);
+
+    let mut lines = Vec::new();
+    let mut next_offset = 0;
+    for var in split_top_level(types, ',') {
+        let (ty, offset) = match var.split_once('@') {
+            Some((ty, offset)) => (ty, Some(offset.trim().parse::<usize>()?)),
+            None => (var, None),
+        };
+        let field = ABIField::from_str(ty.trim())?;
+        let size = match field.packed_size() {
+            Ok(Some(size)) if !field.is_composite() => size,
+            _ => return Err(ABIError::InvalidFieldType(field.to_string()).into()),
+        };
+
+        let offset = offset.unwrap_or(next_offset);
+        let end = offset.checked_add(size).filter(|end| *end <= 32)
+            .ok_or_else(|| ABIError::InvalidData(format!("{} at offset {} does not fit in the slot", field, offset)))?;
+        lines.push(field.decode_packed(&word[32 - end..32 - offset])?.to_string());
+        next_offset = end;
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn execute_lookup_selector_cmd(selector: &str, offline: bool) -> util::Res<String> {
+    let data = decode_hex(selector).map_err(|_| CmdError::InvalidArgument(format!("{} is not a hex-encoded selector", selector)))?;
+    if data.len() != 4 {
+        return Err(CmdError::InvalidArgument(format!("{} is not a 4-byte selector", selector)).into());
+    }
+    let selector = encode_hex(&data);
+
+    let remote = if offline { Vec::new() } else {
+        lookup_remote_signature("function", &selector).unwrap_or_else(|e| {
+            eprintln!("warning: selector lookup failed, using bundled signatures: {}", e);
+            Vec::new()
+        })
+    };
+    let sigs = if remote.is_empty() {
+        lookup_known_selector(&data).iter().map(|s| s.to_string()).collect()
+    } else {
+        remote
+    };
+
+    if sigs.is_empty() {
+        return Err(ABIError::UnknownSelector(selector).into());
+    }
+    Ok(sigs.join("\n"))
+}
+
+fn execute_decode_revert_cmd(input: &str, sig: Option<&str>, abi: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    if data.is_empty() {
+        return Ok(String::from("Reverted without data"));
+    }
+    if data.len() < 4 {
+        return Err(ABIError::InvalidData(String::from("revert data is shorter than a selector")).into());
+    }
+
+    if data[..4] == ERROR_SELECTOR {
+        let values = decode_sequence(&[&ABIField::String], &data[4..], 0)?;
+        return Ok(format!("Error: {}", values[0]));
+    }
+    if data[..4] == PANIC_SELECTOR {
+        let code = match decode_sequence(&[&ABIField::UintN(256)], &data[4..], 0)?.remove(0) {
+            ABIValue::Uint(code) => code,
+            _ => return Err(ABIError::InvalidData(String::from("panic code is not a uint256")).into()),
+        };
+        let reason = PANIC_CODES.iter()
+            .find(|(c, _)| BigUint::from(*c) == code)
+            .map(|(_, reason)| *reason)
+            .unwrap_or("unknown panic code");
+        return Ok(format!("Panic: 0x{:02x} ({})", code, reason));
+    }
+
+    let candidates = match (sig, abi) {
+        (Some(s), _) => vec![FunctionSignature::from_str(s)?],
+        (None, Some(path)) => load_abi_entries(path, "error")?,
+        (None, None) => Vec::new(),
+    };
+    let err = candidates.into_iter().find(|e| data[..4] == e.selector())
+        .ok_or_else(|| ABIError::UnknownSelector(encode_hex(&data[..4])))?;
+    let values = err.decode_call(&data)?;
+
+    let mut lines = vec![format!("Custom error: {}", err)];
+    lines.extend(values.iter().map(|v| v.labeled()));
+    Ok(lines.join("\n"))
+}
+
+/// Splits packed data back into values. At most one field may be dynamic, in
+/// which case it takes whatever bytes the static fields leave over.
+pub fn decode_abi_packed(types: &str, data: &[u8]) -> Result<Vec<ABIValue>, ABIError> {
+    let fields = parse_types(types)?;
+    let sizes = fields.iter().map(|f| f.packed_size()).collect::<Result<Vec<Option<usize>>, ABIError>>()?;
+    let static_len: usize = sizes.iter().flatten().sum();
+    let dynamic_count = sizes.iter().filter(|s| s.is_none()).count();
+    if dynamic_count > 1 {
+        return Err(ABIError::AmbiguousPacked);
+    }
+    if static_len > data.len() || (dynamic_count == 0 && static_len != data.len()) {
+        return Err(ABIError::InvalidData(format!("expected {} bytes of packed data but got {}", static_len, data.len())));
+    }
+
+    let mut values = Vec::with_capacity(fields.len());
+    let mut pos = 0;
+    for (field, size) in fields.iter().zip(sizes) {
+        let len = size.unwrap_or(data.len() - static_len);
+        values.push(field.decode_packed(&data[pos..pos + len])?);
+        pos += len;
+    }
+    Ok(values)
+}
+
+pub fn decode_abi(types: &str, data: &[u8]) -> Result<Vec<ABIValue>, ABIError> {
+    let fields = parse_types(types)?;
+    let refs: Vec<&ABIField> = fields.iter().collect();
+    decode_sequence(&refs, data, 0)
+}
+
+fn parse_types(types: &str) -> Result<Vec<ABIField>, ABIError> {
+    if types.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    split_top_level(types, ',').into_iter().map(|t| ABIField::from_str(t.trim())).collect()
+}
+
+fn parse_fields(data: &str) -> Result<Vec<(ABIField, ABIValue)>, ABIError> {
+    split_top_level(data, ',').into_iter().map(parse_field).collect()
+}
+
+/// Parses a JSON array of `{"type": ..., "value": ...}` objects.
+fn parse_json_fields(data: &str) -> Result<Vec<(ABIField, ABIValue)>, ABIError> {
+    let json: Value = serde_json::from_str(data).map_err(|e| ABIError::InvalidJsonInput(e.to_string()))?;
+    let items = json.as_array()
+        .ok_or_else(|| ABIError::InvalidJsonInput(String::from("expected an array of {\"type\", \"value\"} objects")))?;
+
+    items.iter().map(|item| {
+        let ty = item["type"].as_str()
+            .ok_or_else(|| ABIError::InvalidJsonInput(format!("{} is missing a type", item)))?;
+        let field = ABIField::from_str(ty)?;
+        let value = field.parse_json_value(&item["value"])?;
+        Ok((field, value))
+    }).collect()
+}
+
+pub fn encode_abi(data: &str) -> Result<Vec<u8>, ABIError> {
+    encode_fields(parse_fields(data)?)
+}
+
+fn encode_fields(fields: Vec<(ABIField, ABIValue)>) -> Result<Vec<u8>, ABIError> {
+    let (fields, values): (Vec<ABIField>, Vec<ABIValue>) = fields.into_iter().unzip();
+    let refs: Vec<&ABIField> = fields.iter().collect();
+    encode_sequence(&refs, &values)
 }
 
 pub fn encode_abi_packed(data: &str) -> Result<Vec<u8>, ABIError> {
-    let fields = data.split(",");
+    encode_fields_packed(parse_fields(data)?)
+}
+
+fn encode_fields_packed(fields: Vec<(ABIField, ABIValue)>) -> Result<Vec<u8>, ABIError> {
     let mut buf: Vec<u8> = Vec::new();
 
-    for field in fields {
-        encode_field(field, &mut buf)?
+    for (field, value) in fields {
+        field.encode_packed(&value, &mut buf)?
     }
 
     Ok(buf)
 }
 
-pub fn encode_field(field: &str, buf: &mut Vec<u8>) -> Result<(), ABIError> {
-    let values: Vec<&str> = field.split(":").collect();
+fn parse_field(field: &str) -> Result<(ABIField, ABIValue), ABIError> {
+    let values: Vec<&str> = field.splitn(2, ':').collect();
     if values.len() != 2 {
         return Err(ABIError::InvalidFieldDefinition);
     }
 
-    ABIField::from_str(values[0])?
-        .encode_packed(values[1], buf)?;
+    let abi_field = ABIField::from_str(values[0].trim())?;
+    let value = abi_field.parse_value(values[1])?;
+    Ok((abi_field, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packed(input: &str) -> String {
+        encode_hex(&encode_abi_packed(input).unwrap())
+    }
+
+    /// Checks `input` against a reference encoding produced by ethers-rs's
+    /// `abi::encode`, then decodes it as `types` and checks it encodes back to
+    /// the same bytes.
+    fn assert_round_trip(types: &str, input: &str, expected: &str) -> Vec<ABIValue> {
+        let encoded = encode_abi(input).unwrap();
+        assert_eq!(hex::encode(&encoded), expected);
 
-    Ok(())
-}
\ No newline at end of file
+        let fields = parse_types(types).unwrap();
+        let refs: Vec<&ABIField> = fields.iter().collect();
+        let values = decode_abi(types, &encoded).unwrap();
+        assert_eq!(encode_sequence(&refs, &values).unwrap(), encoded);
+        values
+    }
+
+    #[test]
+    fn round_trip_nested_dynamic_array() {
+        let values = assert_round_trip("uint256[][]", "uint256[][]:[[1,2],[3]]", concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+        ));
+        assert_eq!(values[0].to_string(), "[[1, 2], [3]]");
+    }
+
+    #[test]
+    fn round_trip_array_of_static_tuples() {
+        assert_round_trip(
+            "(address,uint96)[]",
+            "(address,uint96)[]:[(0x1111111111111111111111111111111111111111,250),(0x2222222222222222222222222222222222222222,79228162514264337593543950335)]",
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000001111111111111111111111111111111111111111",
+                "00000000000000000000000000000000000000000000000000000000000000fa",
+                "0000000000000000000000002222222222222222222222222222222222222222",
+                "0000000000000000000000000000000000000000ffffffffffffffffffffffff",
+            ));
+    }
+
+    #[test]
+    fn round_trip_nested_tuple_with_dynamic_members() {
+        assert_round_trip(
+            "(uint8,(address,bytes),string[])",
+            "(uint8,(address,bytes),string[]):(7,(0xabababababababababababababababababababab,0xdeadbeef),[a,bc])",
+            concat!(
+                "0000000000000000000000000000000000000000000000000000000000000020",
+                "0000000000000000000000000000000000000000000000000000000000000007",
+                "0000000000000000000000000000000000000000000000000000000000000060",
+                "00000000000000000000000000000000000000000000000000000000000000e0",
+                "000000000000000000000000abababababababababababababababababababab",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000004",
+                "deadbeef00000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000040",
+                "0000000000000000000000000000000000000000000000000000000000000080",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "6100000000000000000000000000000000000000000000000000000000000000",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "6263000000000000000000000000000000000000000000000000000000000000",
+            ));
+    }
+
+    /// The worked example for g(uint256[][],string[]) from the Solidity ABI specification.
+    #[test]
+    fn encode_matches_solidity_spec_example() {
+        let encoded = encode_abi("uint256[][]:[[1,2],[3]],string[]:[one,two,three]").unwrap();
+        assert_eq!(hex::encode(&encoded), concat!(
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "0000000000000000000000000000000000000000000000000000000000000140",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000002",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "00000000000000000000000000000000000000000000000000000000000000a0",
+            "00000000000000000000000000000000000000000000000000000000000000e0",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "6f6e650000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "74776f0000000000000000000000000000000000000000000000000000000000",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "7468726565000000000000000000000000000000000000000000000000000000",
+        ));
+    }
+
+    #[test]
+    fn encode_packed_sign_extends_intn() {
+        assert_eq!(packed("int256:-1"), format!("0x{}", "ff".repeat(32)));
+        assert_eq!(packed("int8:-128"), "0x80");
+        assert_eq!(packed("int16:-1"), "0xffff");
+        assert_eq!(packed("int32:258"), "0x00000102");
+        assert_eq!(packed("int8:127"), "0x7f");
+        assert_eq!(packed("int24:-2,int8:1"), "0xfffffe01");
+    }
+
+    #[test]
+    fn encode_packed_ufixed_uses_full_width() {
+        assert_eq!(packed("ufixed8x1:25.5"), "0xff");
+        assert_eq!(packed("ufixed8x0:200"), "0xc8");
+        assert_eq!(packed("ufixed16x2:655.35"), "0xffff");
+        assert_eq!(packed("ufixed256x18:115792089237316195423570985008687907853269984665640564039457.584007913129639935"),
+                   format!("0x{}", "ff".repeat(32)));
+        assert!(encode_abi_packed("ufixed8x1:25.6").is_err());
+    }
+
+    #[test]
+    fn parse_integer_bounds_exponents_before_allocating() {
+        assert_eq!(parse_integer("1.5e3").unwrap(), BigInt::from(1500));
+        assert_eq!(parse_integer("0e99999999999999999").unwrap(), BigInt::from(0));
+        assert_eq!(parse_integer("1e77").unwrap().to_string(), format!("1{}", "0".repeat(77)));
+        assert!(parse_integer("1e78").is_err());
+        assert!(parse_integer("1e99999999999999999").is_err());
+        assert!(encode_abi("uint256:1e999999999").is_err());
+    }
+
+    #[test]
+    fn constructor_keyword_needs_a_word_boundary() {
+        assert_eq!(split_fragment_kind("constructor(uint256)"), ("constructor", "(uint256)"));
+        assert_eq!(split_fragment_kind("constructor (uint256)"), ("constructor", " (uint256)"));
+        let sig: FunctionSignature = "constructorArgs(uint256)".parse().unwrap();
+        assert_eq!(sig.to_string(), "constructorArgs(uint256)");
+    }
+}
@@ -1,10 +1,61 @@
-use clap::{App, SubCommand, Arg, ArgMatches};
+use clap::{App, SubCommand, Arg, ArgGroup, ArgMatches};
 use crate::util;
-use crate::util::{CmdError, encode_hex};
-use secp256k1::Secp256k1;
-use rand::OsRng;
-use crypto::sha3::Sha3;
-use crypto::digest::Digest;
+use crate::chain::{find_chain, load_chains, ChainError};
+use crate::rpc::{self, make_rpc_url_arg, RpcError};
+use serde_json::{json, Map, Value};
+use crate::util::{CmdError, decode_hex, encode_hex, read_hex_input};
+use crate::crypto::{derive_bip32_key, encrypt_keystore, entropy_to_mnemonic, validate_mnemonic, keccak256, mnemonic_to_seed, parse_derivation_path, parse_private_key, parse_sec1_public_key, public_key_to_address};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::{error, fmt, thread};
+use std::ops::Range;
+use num_bigint::BigUint;
+use std::sync::{mpsc, Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::fs;
+use std::path::Path;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::collections::HashSet;
+
+#[derive(Debug)]
+pub enum AddressError {
+    InvalidAddress(String),
+    ChecksumMismatch(String, String),
+    InvalidIcap(String, &'static str),
+    InvalidTopic(String),
+    InvalidMetaAddress(String),
+    NoLabel(String),
+    InvalidLabelStore(String),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            AddressError::InvalidAddress(a) => write!(f, "{} is not a valid address", a),
+            AddressError::InvalidIcap(a, reason) => write!(f, "{} is not a valid ICAP address: {}", a, reason),
+            AddressError::InvalidTopic(t) => write!(f, "{} is not an address left-padded to 32 bytes", t),
+            AddressError::InvalidMetaAddress(a) => write!(f, "{} is not a valid stealth meta-address", a),
+            AddressError::NoLabel(a) => write!(f, "{} has no label", a),
+            AddressError::InvalidLabelStore(e) => write!(f, "invalid label store: {}", e),
+            AddressError::ChecksumMismatch(a, expected) => write!(f, "{} is not correctly checksummed; expected {}", a, expected),
+        }
+    }
+}
+
+impl error::Error for AddressError {}
+
+/// Keypairs are handed from generator threads to the writer in batches of this size.
+const GENERATE_BATCH_SIZE: u64 = 1024;
+
+/// The mnemonic Hardhat and Anvil derive their default accounts from.
+const TEST_MNEMONIC: &str = "test test test test test test test test test test test junk";
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0";
+/// Labels are stored as a JSON object mapping lowercase addresses to names.
+const LABELS_FILE: &str = "labels.json";
+/// Indices at or above this are hardened and cannot be appended to a path.
+const MAX_ACCOUNT_INDEX: u32 = 1 << 31;
 
 pub fn make_address_cmd<'a, 'b>() -> App<'a, 'b> {
     let generate_cmd = SubCommand::with_name("generate")
@@ -14,43 +65,1134 @@ pub fn make_address_cmd<'a, 'b>() -> App<'a, 'b> {
             .required(true)
             .takes_value(true)
             .default_value("1"))
+        .arg(Arg::with_name("threads")
+            .short("-t")
+            .long("threads")
+            .takes_value(true)
+            .help("Number of worker threads. Defaults to the number of available CPUs."))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .takes_value(true)
+            .possible_values(&["plain", "json", "csv", "table"])
+            .default_value("plain")
+            .help("The output format."))
+        .arg(Arg::with_name("mnemonic")
+            .long("mnemonic")
+            .help("Creates a new BIP-39 mnemonic and derives the accounts from it along m/44'/60'/0'/0/i, printing the phrase and addresses instead of private keys."))
+        .arg(Arg::with_name("words")
+            .long("words")
+            .takes_value(true)
+            .possible_values(&["12", "15", "18", "21", "24"])
+            .default_value("12")
+            .help("The number of words in a generated mnemonic."))
+        .arg(Arg::with_name("keystore-dir")
+            .long("keystore-dir")
+            .takes_value(true)
+            .requires("password-file")
+            .conflicts_with_all(&["mnemonic", "format"])
+            .help("Writes each account to this directory as an encrypted V3 JSON keystore instead of printing its private key."))
+        .arg(Arg::with_name("password-file")
+            .long("password-file")
+            .takes_value(true)
+            .requires("keystore-dir")
+            .help("A file whose first line is the password the keystores are encrypted with."))
         .about("Generates a set of Ethereum addresses. Outputs both the address and its private key.");
 
+    let test_accounts_cmd = SubCommand::with_name("test-accounts")
+        .arg(Arg::with_name("count")
+            .short("-c")
+            .long("count")
+            .takes_value(true)
+            .default_value("10")
+            .help("Number of accounts to derive."))
+        .arg(Arg::with_name("mnemonic")
+            .long("mnemonic")
+            .takes_value(true)
+            .default_value(TEST_MNEMONIC)
+            .help("The mnemonic to derive accounts from."))
+        .arg(Arg::with_name("path")
+            .long("path")
+            .takes_value(true)
+            .default_value(DEFAULT_DERIVATION_PATH)
+            .help("The derivation path prefix. The account index is appended to it."))
+        .about("Derives the well-known test accounts used by Hardhat and Anvil. Outputs both the address and its private key.");
+
+    let derive_cmd = SubCommand::with_name("derive")
+        .arg(Arg::with_name("mnemonic")
+            .long("mnemonic")
+            .required(true)
+            .takes_value(true)
+            .help("The BIP-39 mnemonic to derive addresses from."))
+        .arg(Arg::with_name("passphrase")
+            .long("passphrase")
+            .takes_value(true)
+            .default_value("")
+            .help("The optional BIP-39 passphrase."))
+        .arg(Arg::with_name("path")
+            .long("path")
+            .takes_value(true)
+            .default_value(DEFAULT_DERIVATION_PATH)
+            .help("The derivation path prefix. The account index is appended to it."))
+        .arg(Arg::with_name("range")
+            .long("range")
+            .takes_value(true)
+            .default_value("0..10")
+            .help("The account indices to derive, as start..end (exclusive) or a single index."))
+        .arg(Arg::with_name("private-keys")
+            .long("private-keys")
+            .help("Also prints each account's private key."))
+        .about("Lists the addresses derived from a mnemonic along a derivation path");
+
+    let to_icap_cmd = SubCommand::with_name("to-icap")
+        .arg(Arg::with_name("address")
+            .help("the address to convert")
+            .index(1)
+            .required(true))
+        .about("Converts an address to its direct or basic ICAP form");
+    let from_icap_cmd = SubCommand::with_name("from-icap")
+        .arg(Arg::with_name("icap")
+            .help("the ICAP address to convert")
+            .index(1)
+            .required(true))
+        .about("Converts a direct or basic ICAP address to a checksummed address, validating its check digits");
+
+    let to_chain_prefixed_cmd = SubCommand::with_name("to-chain-prefixed")
+        .arg(Arg::with_name("address")
+            .help("the address to convert")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("chain")
+            .long("chain")
+            .required(true)
+            .takes_value(true)
+            .help("The chain's ID, name, or short name."))
+        .about("Converts an address to the EIP-3770 shortName:address form, e.g. oeth:0x...");
+    let from_chain_prefixed_cmd = SubCommand::with_name("from-chain-prefixed")
+        .arg(Arg::with_name("address")
+            .help("the shortName:address to convert")
+            .index(1)
+            .required(true))
+        .about("Splits an EIP-3770 shortName:address into its chain and checksummed address");
+
+    let to_topic_cmd = SubCommand::with_name("to-topic")
+        .arg(Arg::with_name("address")
+            .help("the address to convert")
+            .index(1)
+            .required(true))
+        .about("Left-pads an address to a 32-byte log topic, as used for indexed address parameters in eth_getLogs filters");
+    let from_topic_cmd = SubCommand::with_name("from-topic")
+        .arg(Arg::with_name("topic")
+            .help("the 32-byte topic to convert")
+            .index(1)
+            .required(true))
+        .about("Extracts the checksummed address from a 32-byte log topic");
+
+    let checksum_cmd = SubCommand::with_name("checksum")
+        .arg(Arg::with_name("address")
+            .help("the address to checksum")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("verify")
+            .long("verify")
+            .help("Exits with an error unless the address is already correctly checksummed."))
+        .about("Outputs the EIP-55 checksummed form of an address");
+
+    let grep_cmd = SubCommand::with_name("grep")
+        .arg(Arg::with_name("file")
+            .help("the file to search. Reads stdin if omitted.")
+            .index(1))
+        .arg(Arg::with_name("valid-checksum")
+            .long("valid-checksum")
+            .help("Only outputs addresses that appear in mixed case with a valid EIP-55 checksum."))
+        .about("Prints every distinct address found in text such as logs, HTML, or JSON, checksummed");
+
+    let batch_cmd = SubCommand::with_name("batch")
+        .arg(Arg::with_name("checksum")
+            .long("checksum")
+            .conflicts_with("validate")
+            .help("Outputs each address in EIP-55 checksummed form, failing on the first line that is not an address."))
+        .arg(Arg::with_name("validate")
+            .long("validate")
+            .help("Outputs each line followed by a tab and whether it is a valid, correctly checksummed address."))
+        .arg(Arg::with_name("dedupe")
+            .long("dedupe")
+            .help("Drops addresses that already appeared earlier in the list, ignoring case."))
+        .group(ArgGroup::with_name("mode")
+            .args(&["checksum", "validate", "dedupe"])
+            .multiple(true)
+            .required(true))
+        .about("Processes a newline-separated list of addresses from stdin. Blank lines are skipped.");
+
+    let compare_cmd = SubCommand::with_name("compare")
+        .arg(Arg::with_name("a")
+            .help("the first address")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("b")
+            .help("the second address")
+            .index(2)
+            .required(true))
+        .about("Compares two addresses character by character, flagging lookalikes used in address-poisoning attacks");
+
+    let is_contract_cmd = SubCommand::with_name("is-contract")
+        .arg(Arg::with_name("address")
+            .help("the address to check")
+            .index(1)
+            .required(true))
+        .arg(make_rpc_url_arg())
+        .arg(Arg::with_name("block")
+            .long("block")
+            .takes_value(true)
+            .default_value("latest")
+            .help("The block to check the code at."))
+        .about("Reports whether an address has code, along with its size and keccak256 hash, using eth_getCode");
+
+    let from_private_key_cmd = SubCommand::with_name("from-private-key")
+        .arg(Arg::with_name("private-key")
+            .short("-k")
+            .required(true)
+            .takes_value(true)
+            .help("A hex-encoded private key."))
+        .about("Derives the public key and address controlled by a private key");
+
+    let from_public_key_cmd = SubCommand::with_name("from-public-key")
+        .arg(Arg::with_name("public-key")
+            .help("the hex-encoded compressed, uncompressed, or raw 64-byte public key")
+            .index(1)
+            .required(true))
+        .about("Derives the address of a public key");
+
+    let compute_create2_cmd = SubCommand::with_name("compute-create2")
+        .arg(Arg::with_name("deployer")
+            .long("deployer")
+            .required(true)
+            .takes_value(true)
+            .help("The address of the deploying contract or factory."))
+        .arg(Arg::with_name("salt")
+            .long("salt")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded 32-byte salt."))
+        .arg(Arg::with_name("init-code")
+            .long("init-code")
+            .takes_value(true)
+            .required_unless("init-code-hash")
+            .conflicts_with("init-code-hash")
+            .help("The hex-encoded creation bytecode, including constructor arguments. If - is provided, will read from stdin."))
+        .arg(Arg::with_name("init-code-hash")
+            .long("init-code-hash")
+            .takes_value(true)
+            .help("The keccak256 hash of the creation bytecode."))
+        .about("Computes the address of a contract deployed with CREATE2");
+
+    let mine_create2_cmd = SubCommand::with_name("mine-create2")
+        .arg(Arg::with_name("deployer")
+            .long("deployer")
+            .required(true)
+            .takes_value(true)
+            .help("The address of the deploying contract or factory."))
+        .arg(Arg::with_name("init-code")
+            .long("init-code")
+            .takes_value(true)
+            .required_unless("init-code-hash")
+            .conflicts_with("init-code-hash")
+            .help("The hex-encoded creation bytecode, including constructor arguments. If - is provided, will read from stdin."))
+        .arg(Arg::with_name("init-code-hash")
+            .long("init-code-hash")
+            .takes_value(true)
+            .help("The keccak256 hash of the creation bytecode."))
+        .arg(Arg::with_name("prefix")
+            .long("prefix")
+            .takes_value(true)
+            .required_unless("suffix")
+            .help("Hex characters the address must start with, after 0x."))
+        .arg(Arg::with_name("suffix")
+            .long("suffix")
+            .takes_value(true)
+            .help("Hex characters the address must end with."))
+        .arg(Arg::with_name("checksum")
+            .long("checksum")
+            .help("Matches the prefix and suffix case-sensitively against the EIP-55 checksummed address."))
+        .arg(Arg::with_name("threads")
+            .short("-t")
+            .long("threads")
+            .takes_value(true)
+            .help("Number of worker threads. Defaults to the number of available CPUs."))
+        .about("Searches for a CREATE2 salt whose contract address matches a prefix and/or suffix. Outputs both the salt and the address.");
+
+    let vanity_cmd = SubCommand::with_name("vanity")
+        .arg(Arg::with_name("prefix")
+            .long("prefix")
+            .takes_value(true)
+            .required_unless("suffix")
+            .help("Hex characters the address must start with, after 0x."))
+        .arg(Arg::with_name("suffix")
+            .long("suffix")
+            .takes_value(true)
+            .help("Hex characters the address must end with."))
+        .arg(Arg::with_name("checksum")
+            .long("checksum")
+            .help("Matches the prefix and suffix case-sensitively against the EIP-55 checksummed address."))
+        .arg(Arg::with_name("threads")
+            .short("-t")
+            .long("threads")
+            .takes_value(true)
+            .help("Number of worker threads. Defaults to the number of available CPUs."))
+        .about("Searches for a keypair whose address matches a prefix and/or suffix. Outputs both the address and its private key.");
+
+    let label_cmd = SubCommand::with_name("label")
+        .subcommand(SubCommand::with_name("set")
+            .arg(Arg::with_name("address")
+                .help("the address to label")
+                .index(1)
+                .required(true))
+            .arg(Arg::with_name("label")
+                .help("the label, e.g. \"Uniswap Router\"")
+                .index(2)
+                .required(true))
+            .about("Labels an address. Decoded output then shows the label next to the address."))
+        .subcommand(SubCommand::with_name("get")
+            .arg(Arg::with_name("address")
+                .help("the address to look up")
+                .index(1)
+                .required(true))
+            .about("Prints an address's label"))
+        .subcommand(SubCommand::with_name("remove")
+            .arg(Arg::with_name("address")
+                .help("the address to remove the label from")
+                .index(1)
+                .required(true))
+            .about("Removes an address's label"))
+        .subcommand(SubCommand::with_name("list")
+            .about("Lists all labeled addresses"))
+        .about("Manages the local database of address labels");
+
+    let stealth_cmd = SubCommand::with_name("stealth")
+        .subcommand(SubCommand::with_name("meta-address")
+            .arg(Arg::with_name("spending-key")
+                .long("spending-key")
+                .takes_value(true)
+                .requires("viewing-key")
+                .help("The hex-encoded spending private key. Generated if omitted."))
+            .arg(Arg::with_name("viewing-key")
+                .long("viewing-key")
+                .takes_value(true)
+                .requires("spending-key")
+                .help("The hex-encoded viewing private key. Generated if omitted."))
+            .arg(Arg::with_name("chain")
+                .long("chain")
+                .takes_value(true)
+                .default_value("eth")
+                .help("The chain short name to prefix the meta-address with."))
+            .about("Creates a stealth meta-address from a spending and viewing key pair, generating the keys if they are not given"))
+        .subcommand(SubCommand::with_name("generate")
+            .arg(Arg::with_name("meta-address")
+                .long("meta-address")
+                .required(true)
+                .takes_value(true)
+                .help("The recipient's st:<chain>:0x... stealth meta-address."))
+            .arg(Arg::with_name("ephemeral-key")
+                .long("ephemeral-key")
+                .takes_value(true)
+                .help("The hex-encoded ephemeral private key. Generated if omitted."))
+            .about("Derives a one-time stealth address for a recipient. Outputs the address with the ephemeral public key and view tag to announce."))
+        .subcommand(SubCommand::with_name("recover")
+            .arg(Arg::with_name("spending-key")
+                .long("spending-key")
+                .required(true)
+                .takes_value(true)
+                .help("The recipient's hex-encoded spending private key."))
+            .arg(Arg::with_name("viewing-key")
+                .long("viewing-key")
+                .required(true)
+                .takes_value(true)
+                .help("The recipient's hex-encoded viewing private key."))
+            .arg(Arg::with_name("ephemeral-pubkey")
+                .long("ephemeral-pubkey")
+                .required(true)
+                .takes_value(true)
+                .help("The ephemeral public key from the announcement."))
+            .about("Recovers a stealth address and the private key that spends from it. Outputs both the address and its private key."))
+        .about("Generates and recovers ERC-5564 stealth addresses using the secp256k1 scheme");
+
     SubCommand::with_name("address")
+        .subcommand(stealth_cmd)
+        .subcommand(label_cmd)
         .subcommand(generate_cmd)
+        .subcommand(from_private_key_cmd)
+        .subcommand(from_public_key_cmd)
+        .subcommand(derive_cmd)
+        .subcommand(checksum_cmd)
+        .subcommand(compare_cmd)
+        .subcommand(grep_cmd)
+        .subcommand(batch_cmd)
+        .subcommand(is_contract_cmd)
+        .subcommand(to_icap_cmd)
+        .subcommand(from_icap_cmd)
+        .subcommand(to_topic_cmd)
+        .subcommand(from_topic_cmd)
+        .subcommand(to_chain_prefixed_cmd)
+        .subcommand(from_chain_prefixed_cmd)
+        .subcommand(compute_create2_cmd)
+        .subcommand(mine_create2_cmd)
+        .subcommand(vanity_cmd)
+        .subcommand(test_accounts_cmd)
         .about("Generate, manipulate, and validate addresses.")
 }
 
 pub fn execute_address_cmd(matches: &ArgMatches) -> util::Res<String> {
     match matches.subcommand() {
-        ("generate", Some(sub)) => execute_generate_cmd(sub.value_of("count").unwrap()),
+        ("generate", Some(sub)) if sub.is_present("keystore-dir") => execute_generate_keystore_cmd(sub),
+        ("generate", Some(sub)) if sub.is_present("mnemonic") => execute_generate_mnemonic_cmd(sub.value_of("count").unwrap(), sub.value_of("words").unwrap()),
+        ("generate", Some(sub)) => execute_generate_cmd(sub.value_of("count").unwrap(), sub.value_of("threads"), sub.value_of("format").unwrap()),
+        ("from-private-key", Some(sub)) => execute_from_private_key_cmd(sub.value_of("private-key").unwrap()),
+        ("from-public-key", Some(sub)) => {
+            let pub_k = parse_sec1_public_key(sub.value_of("public-key").unwrap())?;
+            Ok(to_checksum_address(&public_key_to_address(&pub_k)))
+        }
+        ("vanity", Some(sub)) => execute_vanity_cmd(sub),
+        ("compute-create2", Some(sub)) => execute_compute_create2_cmd(sub),
+        ("mine-create2", Some(sub)) => execute_mine_create2_cmd(sub),
+        ("derive", Some(sub)) => execute_derive_cmd(sub),
+        ("compare", Some(sub)) => execute_compare_cmd(sub.value_of("a").unwrap(), sub.value_of("b").unwrap()),
+        ("stealth", Some(sub)) => execute_stealth_cmd(sub),
+        ("label", Some(sub)) => execute_label_cmd(sub),
+        ("batch", Some(sub)) => execute_batch_cmd(sub.is_present("checksum"), sub.is_present("validate"), sub.is_present("dedupe")),
+        ("grep", Some(sub)) => execute_grep_cmd(sub.value_of("file"), sub.is_present("valid-checksum")),
+        ("is-contract", Some(sub)) => execute_is_contract_cmd(sub),
+        ("to-icap", Some(sub)) => execute_to_icap_cmd(sub.value_of("address").unwrap()),
+        ("from-icap", Some(sub)) => execute_from_icap_cmd(sub.value_of("icap").unwrap()),
+        ("to-topic", Some(sub)) => execute_to_topic_cmd(sub.value_of("address").unwrap()),
+        ("from-topic", Some(sub)) => execute_from_topic_cmd(sub.value_of("topic").unwrap()),
+        ("to-chain-prefixed", Some(sub)) => execute_to_chain_prefixed_cmd(sub.value_of("address").unwrap(), sub.value_of("chain").unwrap()),
+        ("from-chain-prefixed", Some(sub)) => execute_from_chain_prefixed_cmd(sub.value_of("address").unwrap()),
+        ("checksum", Some(sub)) => execute_checksum_cmd(sub.value_of("address").unwrap(), sub.is_present("verify")),
+        ("test-accounts", Some(sub)) => execute_test_accounts_cmd(sub.value_of("count").unwrap(), sub.value_of("mnemonic").unwrap(), sub.value_of("path").unwrap()),
         (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
     }
 }
 
-fn execute_generate_cmd(input: &str) -> util::Res<String> {
-    let count: u16 = input.parse()?;
+/// Generates keypairs across worker threads and streams them to stdout in
+/// batches, so large counts use bounded memory.
+fn execute_generate_cmd(count: &str, threads: Option<&str>, format: &str) -> util::Res<String> {
+    let count: u64 = count.parse()?;
+    let threads = util::parse_thread_count(threads)?.min(count.max(1) as usize);
+
+    let (tx, rx) = mpsc::sync_channel(threads * 2);
+    for i in 0..threads as u64 {
+        let share = count / threads as u64 + if i < count % threads as u64 { 1 } else { 0 };
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let secp = Secp256k1::new();
+            let mut rng = match OsRng::new() {
+                Ok(rng) => rng,
+                Err(_) => return tx.send(None),
+            };
+            let mut remaining = share;
+            while remaining > 0 {
+                let n = remaining.min(GENERATE_BATCH_SIZE);
+                let batch: Vec<(String, String)> = (0..n).map(|_| {
+                    let (priv_k, pub_k) = secp.generate_keypair(&mut rng);
+                    (format!("0x{}", priv_k), to_checksum_address(&public_key_to_address(&pub_k)))
+                }).collect();
+                tx.send(Some(batch))?;
+                remaining -= n;
+            }
+            Ok(())
+        });
+    }
+    drop(tx);
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match format {
+        "json" => write!(out, "[")?,
+        "csv" => writeln!(out, "private_key,address")?,
+        "table" => writeln!(out, "{:<66}  Address\n{}  {}", "Private key", "-".repeat(66), "-".repeat(42))?,
+        _ => {}
+    }
+    let mut first = true;
+    for batch in rx {
+        let batch = batch.ok_or_else(|| CmdError::Unavailable(String::from("the OS random number generator")))?;
+        for (priv_k, addr) in batch {
+            match format {
+                "json" => write!(out, "{}\n  {{\"privateKey\": \"{}\", \"address\": \"{}\"}}", if first { "" } else { "," }, priv_k, addr)?,
+                "csv" => writeln!(out, "{},{}", priv_k, addr)?,
+                "table" => writeln!(out, "{:<66}  {}", priv_k, addr)?,
+                _ => writeln!(out, "{} {}", priv_k, addr)?,
+            }
+            first = false;
+        }
+    }
+    if format == "json" {
+        writeln!(out, "{}]", if first { "" } else { "\n" })?;
+    }
+    out.flush()?;
+    Ok(String::new())
+}
+
+fn execute_generate_mnemonic_cmd(count: &str, words: &str) -> util::Res<String> {
+    let count: u32 = count.parse()?;
+    let words: usize = words.parse()?;
+    let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+    let mut entropy = vec![0; words / 3 * 4];
+    rng.fill_bytes(&mut entropy);
+
+    let mnemonic = entropy_to_mnemonic(&entropy);
+    let mut lines = vec![format!("Mnemonic: {}", mnemonic)];
+    for (i, _, addr) in derive_accounts(&mnemonic, "", DEFAULT_DERIVATION_PATH, 0..count)? {
+        lines.push(format!("{}/{} {}", DEFAULT_DERIVATION_PATH, i, to_checksum_address(&addr)));
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Generates keypairs and writes each one to its own keystore file, named
+/// the way geth names them, printing the addresses and paths.
+fn execute_generate_keystore_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let count: u64 = matches.value_of("count").unwrap().parse()?;
+    let dir = Path::new(matches.value_of("keystore-dir").unwrap());
+    let password_file = matches.value_of("password-file").unwrap();
+    let password = fs::read_to_string(password_file)?;
+    let password = password.lines().next().unwrap_or("");
+    fs::create_dir_all(dir)?;
 
     let secp = Secp256k1::new();
-    let mut s = String::new();
-    let mut h = Sha3::keccak256();
-    let mut rng = OsRng::new().expect("OsRng");
-    for i in 0..count {
+    let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for _ in 0..count {
         let (priv_k, pub_k) = secp.generate_keypair(&mut rng);
-        let ser = pub_k.serialize_uncompressed();
-        let deprefixed = &ser[1..];
-        h.input(deprefixed);
-        let mut out: [u8; 32] = [0; 32];
-        h.result(&mut out);
-        let addr = out[12..].to_vec();
-        h.reset();
+        let addr = public_key_to_address(&pub_k);
+        let keystore = encrypt_keystore(&priv_k, password.as_bytes())?;
+        let path = dir.join(format!("UTC--{}--{}", keystore_timestamp(SystemTime::now()), hex::encode(addr)));
+        fs::write(&path, serde_json::to_string(&keystore)?)?;
+        writeln!(out, "{} {}", to_checksum_address(&addr), path.display())?;
+    }
+    Ok(String::new())
+}
+
+/// Formats a time as geth does in keystore file names, e.g.
+/// `2019-06-01T12-30-00.000000000Z`.
+fn keystore_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let utc = util::format_utc(since_epoch.as_secs());
+    format!("{}.{:09}Z", utc.trim_end_matches('Z').replace(':', "-"), since_epoch.subsec_nanos())
+}
+
+/// Formats an address with its EIP-55 mixed-case checksum.
+pub fn to_checksum_address(addr: &[u8]) -> String {
+    let lower = hex::encode(addr);
+    let hash = keccak256(lower.as_bytes());
+    let checksummed: String = lower.chars().enumerate().map(|(i, c)| {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+    }).collect();
+    format!("0x{}", checksummed)
+}
+
+/// Computes the two ISO 13616 check digits for an IBAN-style string whose
+/// check digits are given as `00`.
+fn iban_check_digits(iban: &str) -> String {
+    let rearranged = format!("{}{}", &iban[4..], &iban[..4]);
+    let remainder = rearranged.chars().fold(0u32, |acc, c| {
+        let value = c.to_digit(36).unwrap();
+        if value < 10 { (acc * 10 + value) % 97 } else { (acc * 100 + value) % 97 }
+    });
+    format!("{:02}", 98 - remainder)
+}
+
+fn execute_to_icap_cmd(input: &str) -> util::Res<String> {
+    let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    let bban = format!("{:0>30}", BigUint::from_bytes_be(&addr).to_str_radix(36).to_uppercase());
+    Ok(format!("XE{}{}", iban_check_digits(&format!("XE00{}", bban)), bban))
+}
+
+fn execute_from_icap_cmd(input: &str) -> util::Res<String> {
+    let icap = input.to_uppercase();
+    let invalid = |reason| AddressError::InvalidIcap(String::from(input), reason);
+    if !icap.starts_with("XE") {
+        return Err(invalid("it must start with XE").into());
+    }
+    if icap.len() == 20 {
+        return Err(invalid("indirect ICAP addresses require a name registry lookup and are not supported").into());
+    }
+    if !(icap.len() == 34 || icap.len() == 35) || !icap.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(invalid("it must be XE, two check digits, and 30 or 31 base-36 characters").into());
+    }
+    if iban_check_digits(&format!("XE00{}", &icap[4..])) != icap[2..4] {
+        return Err(invalid("the check digits do not match").into());
+    }
 
-        s.push_str(format!("0x{} ", priv_k.to_string()).as_str());
-        s.push_str(format!("{}", encode_hex(&addr)).as_str());
-        if i != count - 1 {
-            s.push_str("\n");
+    let value = BigUint::parse_bytes(&icap.as_bytes()[4..], 36).ok_or_else(|| invalid("it is not base-36 encoded"))?;
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 20 {
+        return Err(invalid("it encodes a value larger than an address").into());
+    }
+    let mut addr = [0; 20];
+    addr[20 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(to_checksum_address(&addr))
+}
+
+fn execute_to_topic_cmd(input: &str) -> util::Res<String> {
+    let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    Ok(format!("0x{}{}", "0".repeat(24), hex::encode(addr)))
+}
+
+fn execute_from_topic_cmd(input: &str) -> util::Res<String> {
+    let topic = decode_hex(input).ok().filter(|t| t.len() == 32 && t[..12].iter().all(|b| *b == 0))
+        .ok_or_else(|| AddressError::InvalidTopic(String::from(input)))?;
+    Ok(to_checksum_address(&topic[12..]))
+}
+
+fn execute_to_chain_prefixed_cmd(input: &str, chain: &str) -> util::Res<String> {
+    let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    let chain = find_chain(chain)?;
+    if chain.short_name.is_empty() {
+        return Err(ChainError::UnknownChain(format!("{} with a short name", chain.name)).into());
+    }
+    Ok(format!("{}:{}", chain.short_name, to_checksum_address(&addr)))
+}
+
+fn execute_from_chain_prefixed_cmd(input: &str) -> util::Res<String> {
+    let (short_name, addr_hex) = input.split_once(':')
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    let addr = decode_hex(addr_hex).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(addr_hex)))?;
+    let chain = load_chains()?
+        .into_iter()
+        .find(|c| !c.short_name.is_empty() && c.short_name.eq_ignore_ascii_case(short_name))
+        .ok_or_else(|| ChainError::UnknownChain(String::from(short_name)))?;
+    Ok(format!("Chain: {} ({})\nAddress: {}", chain.name, chain.chain_id, to_checksum_address(&addr)))
+}
+
+fn execute_checksum_cmd(input: &str, verify: bool) -> util::Res<String> {
+    let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    let checksummed = to_checksum_address(&addr);
+    if verify && checksummed[2..] != *input.trim_start_matches("0x") {
+        return Err(AddressError::ChecksumMismatch(String::from(input), checksummed).into());
+    }
+    Ok(checksummed)
+}
+
+/// Finds 0x-prefixed, 40-hex-character runs that are not part of a longer
+/// hex string or identifier.
+fn find_addresses(text: &[u8]) -> Vec<&[u8]> {
+    let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i + 42 <= text.len() {
+        let candidate = &text[i..i + 42];
+        if candidate.starts_with(b"0x") && (i == 0 || !is_word(&text[i - 1]))
+            && candidate[2..].iter().all(|b| b.is_ascii_hexdigit())
+            && text.get(i + 42).is_none_or(|b| !is_word(b)) {
+            found.push(candidate);
+            i += 42;
+        } else {
+            i += 1;
         }
     }
+    found
+}
 
-    Ok(s)
-}
\ No newline at end of file
+fn execute_grep_cmd(file: Option<&str>, valid_checksum: bool) -> util::Res<String> {
+    let reader: Box<dyn BufRead> = match file {
+        Some(path) => Box::new(BufReader::new(fs::File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut seen = HashSet::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        for candidate in find_addresses(&line) {
+            let candidate = std::str::from_utf8(candidate)?;
+            let addr = decode_hex(candidate)?;
+            let checksummed = to_checksum_address(&addr);
+            let is_mixed_case = candidate[2..].chars().any(|c| c.is_ascii_lowercase()) && candidate[2..].chars().any(|c| c.is_ascii_uppercase());
+            if valid_checksum && (!is_mixed_case || checksummed != candidate) {
+                continue;
+            }
+            if seen.insert(addr) {
+                writeln!(out, "{}", checksummed)?;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(String::new())
+}
+
+/// Describes whether an input is a valid address, treating all-lowercase and
+/// all-uppercase addresses as unchecksummed rather than invalid.
+fn validate_address(input: &str) -> &'static str {
+    let addr = match decode_hex(input).ok().filter(|a| a.len() == 20 && input.starts_with("0x")) {
+        Some(addr) => addr,
+        None => return "invalid address",
+    };
+    let digits = &input[2..];
+    if digits == digits.to_lowercase() || digits == digits.to_uppercase() {
+        "valid (not checksummed)"
+    } else if to_checksum_address(&addr) == input {
+        "valid"
+    } else {
+        "invalid checksum"
+    }
+}
+
+fn execute_batch_cmd(checksum: bool, validate: bool, dedupe: bool) -> util::Res<String> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut seen = HashSet::new();
+    for (i, line) in stdin.lock().lines().enumerate() {
+        let line = line?;
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        if dedupe && !seen.insert(input.to_lowercase()) {
+            continue;
+        }
+        if validate {
+            writeln!(out, "{}\t{}", input, validate_address(input))?;
+        } else if checksum {
+            let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+                .ok_or_else(|| CmdError::InvalidInput(format!("line {}: {}", i + 1, AddressError::InvalidAddress(String::from(input)))))?;
+            writeln!(out, "{}", to_checksum_address(&addr))?;
+        } else {
+            writeln!(out, "{}", input)?;
+        }
+    }
+    out.flush()?;
+    Ok(String::new())
+}
+
+/// Wallets usually abbreviate addresses to a few characters at each end, so
+/// different addresses sharing at least this many are worth a warning.
+const LOOKALIKE_THRESHOLD: usize = 6;
+
+fn execute_compare_cmd(a: &str, b: &str) -> util::Res<String> {
+    let parse = |input: &str| decode_hex(input).ok().filter(|a| a.len() == 20)
+        .map(|a| to_checksum_address(&a))
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)));
+    let (a, b) = (parse(a)?, parse(b)?);
+    let matches: Vec<bool> = a[2..].chars().zip(b[2..].chars()).map(|(x, y)| x.eq_ignore_ascii_case(&y)).collect();
+    let prefix = matches.iter().take_while(|m| **m).count();
+    let suffix = matches.iter().rev().take_while(|m| **m).count();
+    let positional = matches.iter().filter(|m| **m).count();
+    let markers: String = matches.iter().map(|m| if *m { '^' } else { ' ' }).collect();
+
+    let verdict = if positional == matches.len() {
+        "The addresses are identical."
+    } else if prefix + suffix >= LOOKALIKE_THRESHOLD {
+        "WARNING: these are different addresses that look alike at the ends, as in address-poisoning attacks. Compare every character before sending funds."
+    } else {
+        "The addresses are different and do not share a notable prefix or suffix."
+    };
+    Ok(format!("A: {}\nB: {}\n     {}\nMatching prefix: {} characters\nMatching suffix: {} characters\nMatching positions: {}/{} ({:.0}%)\n{}",
+               a, b, markers.trim_end(), prefix, suffix, positional, matches.len(),
+               positional as f64 * 100.0 / matches.len() as f64, verdict))
+}
+
+/// EIP-7702 delegated accounts have code consisting of this prefix followed
+/// by the delegate's address.
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
+fn execute_is_contract_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let input = matches.value_of("address").unwrap();
+    let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+    let res = rpc::call(matches.value_of("rpc-url").unwrap(), "eth_getCode",
+                        json!([encode_hex(&addr), matches.value_of("block").unwrap()]))?;
+    let code = res.as_str().and_then(|s| decode_hex(s).ok())
+        .ok_or_else(|| RpcError::InvalidResponse(format!("{} is not hex-encoded code", res)))?;
+
+    let kind = if code.is_empty() {
+        String::from("no (externally owned account or empty)")
+    } else if code.len() == 23 && code.starts_with(&DELEGATION_PREFIX) {
+        let delegate = match address_label(&code[3..]) {
+            Some(label) => format!("{} ({})", to_checksum_address(&code[3..]), label),
+            None => to_checksum_address(&code[3..]),
+        };
+        format!("no, but it is an EIP-7702 delegated account running the code of {}", delegate)
+    } else {
+        String::from("yes")
+    };
+    Ok(format!("Contract: {}\nCode size: {} bytes\nCode hash: {}", kind, code.len(), encode_hex(&keccak256(&code))))
+}
+
+fn execute_from_private_key_cmd(pk_hex: &str) -> util::Res<String> {
+    let pub_k = PublicKey::from_secret_key(&Secp256k1::new(), &parse_private_key(pk_hex)?);
+    Ok(format!("Public key: {}\nAddress: {}",
+               encode_hex(&pub_k.serialize_uncompressed()), to_checksum_address(&public_key_to_address(&pub_k))))
+}
+
+fn parse_fixed_hex(input: &str, len: usize, what: &str) -> util::Res<Vec<u8>> {
+    decode_hex(input).ok().filter(|b| b.len() == len)
+        .ok_or_else(|| CmdError::InvalidArgument(format!("{} is not a {}-byte {}", input, len, what)).into())
+}
+
+/// Computes `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`
+/// as defined by EIP-1014.
+pub fn create2_address(deployer: &[u8], salt: &[u8], init_code_hash: &[u8]) -> [u8; 20] {
+    let mut buf = Vec::with_capacity(85);
+    buf.push(0xff);
+    buf.extend_from_slice(deployer);
+    buf.extend_from_slice(salt);
+    buf.extend_from_slice(init_code_hash);
+    let mut out = [0; 20];
+    out.copy_from_slice(&keccak256(&buf)[12..]);
+    out
+}
+
+/// Reads the deployer and init code hash shared by the CREATE2 commands.
+fn read_create2_args(matches: &ArgMatches) -> util::Res<(Vec<u8>, Vec<u8>)> {
+    let deployer_hex = matches.value_of("deployer").unwrap();
+    let deployer = decode_hex(deployer_hex).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| AddressError::InvalidAddress(String::from(deployer_hex)))?;
+    let init_code_hash = match matches.value_of("init-code") {
+        Some(input) => keccak256(&read_hex_input(input)?).to_vec(),
+        None => parse_fixed_hex(matches.value_of("init-code-hash").unwrap(), 32, "hash")?,
+    };
+    Ok((deployer, init_code_hash))
+}
+
+fn execute_compute_create2_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let (deployer, init_code_hash) = read_create2_args(matches)?;
+    let salt = parse_fixed_hex(matches.value_of("salt").unwrap(), 32, "salt")?;
+    Ok(to_checksum_address(&create2_address(&deployer, &salt, &init_code_hash)))
+}
+
+/// Each thread starts from a random salt and counts up through its last
+/// eight bytes, so threads never try the same salt.
+fn execute_mine_create2_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let (deployer, init_code_hash) = read_create2_args(matches)?;
+    let pattern = Arc::new(VanityPattern::from_matches(matches)?);
+    let threads = util::parse_thread_count(matches.value_of("threads"))?;
+    let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..threads {
+        let (pattern, stop, attempts, tx) = (Arc::clone(&pattern), Arc::clone(&stop), Arc::clone(&attempts), tx.clone());
+        let (deployer, init_code_hash) = (deployer.clone(), init_code_hash.clone());
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt[..24]);
+        thread::spawn(move || {
+            let mut counter: u64 = 0;
+            while !stop.load(Ordering::Relaxed) {
+                salt[24..].copy_from_slice(&counter.to_be_bytes());
+                let addr = create2_address(&deployer, &salt, &init_code_hash);
+                attempts.fetch_add(1, Ordering::Relaxed);
+                if pattern.matches(&addr) {
+                    stop.store(true, Ordering::Relaxed);
+                    let _ = tx.send((salt, addr));
+                }
+                counter = counter.wrapping_add(1);
+            }
+        });
+    }
+    drop(tx);
+
+    let (salt, addr) = await_vanity_match(rx, &attempts, pattern.difficulty())?;
+    Ok(format!("{} {}", encode_hex(&salt), to_checksum_address(&addr)))
+}
+
+struct VanityPattern {
+    prefix: String,
+    suffix: String,
+    checksum: bool,
+}
+
+impl VanityPattern {
+    fn from_matches(matches: &ArgMatches) -> util::Res<VanityPattern> {
+        let checksum = matches.is_present("checksum");
+        let mut parts = Vec::new();
+        for name in &["prefix", "suffix"] {
+            let part = matches.value_of(name).unwrap_or("");
+            let part = if *name == "prefix" { part.trim_start_matches("0x") } else { part };
+            if !part.chars().all(|c| c.is_ascii_hexdigit()) || part.len() > 40 {
+                return Err(CmdError::InvalidArgument(format!("{} is not a valid address {}", part, name)).into());
+            }
+            parts.push(if checksum { String::from(part) } else { part.to_ascii_lowercase() });
+        }
+        let suffix = parts.pop().unwrap();
+        let prefix = parts.pop().unwrap();
+        Ok(VanityPattern { prefix, suffix, checksum })
+    }
+
+    fn matches(&self, addr: &[u8]) -> bool {
+        let formatted = if self.checksum { to_checksum_address(addr) } else { encode_hex(addr) };
+        formatted[2..].starts_with(&self.prefix) && formatted.ends_with(&self.suffix)
+    }
+
+    /// The expected number of attempts before a match.
+    fn difficulty(&self) -> f64 {
+        let pattern = format!("{}{}", self.prefix, self.suffix);
+        let letters = if self.checksum { pattern.chars().filter(|c| c.is_ascii_alphabetic()).count() } else { 0 };
+        16f64.powi(pattern.len() as i32) * 2f64.powi(letters as i32)
+    }
+}
+
+fn execute_vanity_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let pattern = Arc::new(VanityPattern::from_matches(matches)?);
+    let threads = util::parse_thread_count(matches.value_of("threads"))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let attempts = Arc::new(AtomicU64::new(0));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..threads {
+        let (pattern, stop, attempts, tx) = (Arc::clone(&pattern), Arc::clone(&stop), Arc::clone(&attempts), tx.clone());
+        thread::spawn(move || {
+            let secp = Secp256k1::new();
+            let mut rng = match OsRng::new() {
+                Ok(rng) => rng,
+                Err(_) => return,
+            };
+            while !stop.load(Ordering::Relaxed) {
+                let (priv_k, pub_k) = secp.generate_keypair(&mut rng);
+                let addr = public_key_to_address(&pub_k);
+                attempts.fetch_add(1, Ordering::Relaxed);
+                if pattern.matches(&addr) {
+                    stop.store(true, Ordering::Relaxed);
+                    let _ = tx.send((priv_k, addr));
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let (priv_k, addr) = await_vanity_match(rx, &attempts, pattern.difficulty())?;
+    Ok(format!("0x{} {}", priv_k, to_checksum_address(&addr)))
+}
+
+/// Waits for a search's worker threads to report a match, printing progress
+/// to stderr while they run.
+fn await_vanity_match<T>(rx: mpsc::Receiver<T>, attempts: &AtomicU64, difficulty: f64) -> util::Res<T> {
+    let start = Instant::now();
+    loop {
+        match rx.recv_timeout(Duration::from_secs(2)) {
+            Ok(found) => {
+                eprintln!("Found after {} attempts in {:.1}s", attempts.load(Ordering::Relaxed), start.elapsed().as_secs_f64());
+                return Ok(found);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                let n = attempts.load(Ordering::Relaxed);
+                let elapsed = start.elapsed().as_secs_f64();
+                eprintln!("{} attempts ({:.0}/sec, {:.1}% of the expected {:.0})",
+                          n, n as f64 / elapsed, n as f64 / difficulty * 100.0, difficulty);
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(CmdError::Unavailable(String::from("the OS random number generator")).into());
+            }
+        }
+    }
+}
+
+fn read_labels() -> util::Res<Map<String, Value>> {
+    let data = match fs::read_to_string(util::data_path(LABELS_FILE)?) {
+        Ok(data) => data,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Map::new()),
+        Err(e) => return Err(e.into()),
+    };
+    match serde_json::from_str(&data).map_err(|e| AddressError::InvalidLabelStore(e.to_string()))? {
+        Value::Object(labels) => Ok(labels),
+        _ => Err(AddressError::InvalidLabelStore(String::from("expected a JSON object of labels")).into()),
+    }
+}
+
+fn write_labels(labels: &Map<String, Value>) -> util::Res<()> {
+    let path = util::data_path(LABELS_FILE)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(labels)?)?;
+    Ok(())
+}
+
+/// Looks up an address's label. The label store is read once per process.
+pub fn address_label(addr: &[u8]) -> Option<&'static str> {
+    static LABELS: OnceLock<Map<String, Value>> = OnceLock::new();
+    LABELS.get_or_init(|| read_labels().unwrap_or_default()).get(&encode_hex(addr)).and_then(|l| l.as_str())
+}
+
+/// Formats an address for display, followed by its label if it has one.
+pub fn label_address(addr: &[u8]) -> String {
+    match address_label(addr) {
+        Some(label) => format!("{} ({})", encode_hex(addr), label),
+        None => encode_hex(addr),
+    }
+}
+
+fn execute_label_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let parse = |sub: &ArgMatches| -> util::Res<String> {
+        let input = sub.value_of("address").unwrap();
+        let addr = decode_hex(input).ok().filter(|a| a.len() == 20)
+            .ok_or_else(|| AddressError::InvalidAddress(String::from(input)))?;
+        Ok(encode_hex(&addr))
+    };
+    let mut labels = read_labels()?;
+    match matches.subcommand() {
+        ("set", Some(sub)) => {
+            labels.insert(parse(sub)?, Value::String(String::from(sub.value_of("label").unwrap())));
+            write_labels(&labels)?;
+            Ok(String::new())
+        }
+        ("get", Some(sub)) => {
+            let addr = parse(sub)?;
+            labels.get(&addr).and_then(|l| l.as_str()).map(String::from)
+                .ok_or_else(|| AddressError::NoLabel(addr).into())
+        }
+        ("remove", Some(sub)) => {
+            let addr = parse(sub)?;
+            labels.remove(&addr).ok_or(AddressError::NoLabel(addr))?;
+            write_labels(&labels)?;
+            Ok(String::new())
+        }
+        ("list", Some(_)) => {
+            let lines: Vec<String> = labels.iter()
+                .map(|(addr, label)| format!("{} {}", decode_hex(addr).map(|a| to_checksum_address(&a)).unwrap_or_else(|_| addr.clone()), label.as_str().unwrap_or("")))
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_stealth_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("meta-address", Some(sub)) => execute_stealth_meta_address_cmd(sub),
+        ("generate", Some(sub)) => execute_stealth_generate_cmd(sub.value_of("meta-address").unwrap(), sub.value_of("ephemeral-key")),
+        ("recover", Some(sub)) => execute_stealth_recover_cmd(sub.value_of("spending-key").unwrap(), sub.value_of("viewing-key").unwrap(), sub.value_of("ephemeral-pubkey").unwrap()),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+/// Computes the ERC-5564 hashed shared secret: the keccak256 hash of the
+/// compressed ECDH point.
+fn stealth_shared_hash(priv_k: &SecretKey, pub_k: &PublicKey) -> util::Res<[u8; 32]> {
+    let mut point = *pub_k;
+    point.mul_assign(&Secp256k1::new(), &priv_k[..])?;
+    Ok(keccak256(&point.serialize()))
+}
+
+/// Parses a `st:<chain>:0x...` meta-address into its spending and viewing
+/// public keys.
+fn parse_stealth_meta_address(input: &str) -> util::Res<(PublicKey, PublicKey)> {
+    let invalid = || AddressError::InvalidMetaAddress(String::from(input));
+    let keys_hex = match input.strip_prefix("st:") {
+        Some(rest) => rest.split_once(':').ok_or_else(invalid)?.1,
+        None => input,
+    };
+    let keys = decode_hex(keys_hex).ok().filter(|k| k.len() == 66).ok_or_else(invalid)?;
+    let spending = PublicKey::from_slice(&keys[..33]).map_err(|_| invalid())?;
+    let viewing = PublicKey::from_slice(&keys[33..]).map_err(|_| invalid())?;
+    Ok((spending, viewing))
+}
+
+fn execute_stealth_meta_address_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let secp = Secp256k1::new();
+    let (spending, viewing) = match (matches.value_of("spending-key"), matches.value_of("viewing-key")) {
+        (Some(spending), Some(viewing)) => (parse_private_key(spending)?, parse_private_key(viewing)?),
+        _ => {
+            let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+            (secp.generate_keypair(&mut rng).0, secp.generate_keypair(&mut rng).0)
+        }
+    };
+    let mut keys = PublicKey::from_secret_key(&secp, &spending).serialize().to_vec();
+    keys.extend_from_slice(&PublicKey::from_secret_key(&secp, &viewing).serialize());
+    Ok(format!("Spending key: 0x{}\nViewing key: 0x{}\nMeta-address: st:{}:{}",
+               spending, viewing, matches.value_of("chain").unwrap(), encode_hex(&keys)))
+}
+
+fn execute_stealth_generate_cmd(meta_address: &str, ephemeral_key: Option<&str>) -> util::Res<String> {
+    let secp = Secp256k1::new();
+    let (mut stealth_pub, viewing) = parse_stealth_meta_address(meta_address)?;
+    let ephemeral = match ephemeral_key {
+        Some(pk_hex) => parse_private_key(pk_hex)?,
+        None => {
+            let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+            secp.generate_keypair(&mut rng).0
+        }
+    };
+    let hash = stealth_shared_hash(&ephemeral, &viewing)?;
+    stealth_pub.add_exp_assign(&secp, &hash)?;
+    Ok(format!("Stealth address: {}\nEphemeral public key: {}\nView tag: 0x{:02x}",
+               to_checksum_address(&public_key_to_address(&stealth_pub)),
+               encode_hex(&PublicKey::from_secret_key(&secp, &ephemeral).serialize()), hash[0]))
+}
+
+fn execute_stealth_recover_cmd(spending_hex: &str, viewing_hex: &str, ephemeral_hex: &str) -> util::Res<String> {
+    let mut stealth_priv = parse_private_key(spending_hex)?;
+    let viewing = parse_private_key(viewing_hex)?;
+    let ephemeral = parse_sec1_public_key(ephemeral_hex)?;
+    let hash = stealth_shared_hash(&viewing, &ephemeral)?;
+    stealth_priv.add_assign(&hash)?;
+    let stealth_pub = PublicKey::from_secret_key(&Secp256k1::new(), &stealth_priv);
+    Ok(format!("0x{} {}\nView tag: 0x{:02x}", stealth_priv, to_checksum_address(&public_key_to_address(&stealth_pub)), hash[0]))
+}
+
+fn execute_test_accounts_cmd(count: &str, mnemonic: &str, path: &str) -> util::Res<String> {
+    let count: u32 = count.parse()?;
+    let accounts = derive_accounts(mnemonic, "", path, 0..count)?;
+    Ok(accounts.iter()
+        .map(|(_, priv_k, addr)| format!("0x{} {}", priv_k, to_checksum_address(addr)))
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+fn execute_derive_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let mnemonic = matches.value_of("mnemonic").unwrap();
+    validate_mnemonic(mnemonic)?;
+    let path = matches.value_of("path").unwrap();
+    let range = parse_index_range(matches.value_of("range").unwrap())?;
+    let accounts = derive_accounts(mnemonic, matches.value_of("passphrase").unwrap(), path, range)?;
+
+    Ok(accounts.iter().map(|(i, priv_k, addr)| {
+        let line = format!("{}/{} {}", path, i, to_checksum_address(addr));
+        if matches.is_present("private-keys") { format!("{} 0x{}", line, priv_k) } else { line }
+    }).collect::<Vec<String>>().join("\n"))
+}
+
+/// Parses `start..end` (end exclusive) or a single index.
+fn parse_index_range(range: &str) -> util::Res<Range<u32>> {
+    let invalid = || CmdError::InvalidArgument(format!("{} is not a valid index range", range));
+    match range.split_once("..") {
+        Some((start, end)) => {
+            let start: u32 = start.parse().map_err(|_| invalid())?;
+            let end: u32 = end.parse().map_err(|_| invalid())?;
+            if start >= end || end > MAX_ACCOUNT_INDEX {
+                return Err(invalid().into());
+            }
+            Ok(start..end)
+        }
+        None => {
+            let index: u32 = range.parse().map_err(|_| invalid())?;
+            if index >= MAX_ACCOUNT_INDEX {
+                return Err(invalid().into());
+            }
+            Ok(index..index + 1)
+        }
+    }
+}
+
+/// Derives the private key and address at each index under a path prefix.
+fn derive_accounts(mnemonic: &str, passphrase: &str, path: &str, indices: Range<u32>) -> util::Res<Vec<(u32, SecretKey, [u8; 20])>> {
+    let seed = mnemonic_to_seed(mnemonic, passphrase);
+    let prefix = parse_derivation_path(path)?;
+    let secp = Secp256k1::new();
+
+    let mut accounts = Vec::new();
+    for i in indices {
+        let mut child_path = prefix.clone();
+        child_path.push(i);
+        let priv_k = derive_bip32_key(&seed, &child_path)?;
+        let addr = public_key_to_address(&PublicKey::from_secret_key(&secp, &priv_k));
+        accounts.push((i, priv_k, addr));
+    }
+    Ok(accounts)
+}
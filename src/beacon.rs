@@ -0,0 +1,134 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::CmdError;
+use std::{error, fmt};
+
+const MAINNET_GENESIS_TIME: &str = "1606824023";
+const MAINNET_SECONDS_PER_SLOT: &str = "12";
+const MAINNET_SLOTS_PER_EPOCH: &str = "32";
+
+#[derive(Debug)]
+pub enum BeaconError {
+    BeforeGenesis(u64),
+    InvalidSlotDuration,
+    SlotOutOfRange(u64),
+    TimestampOutOfRange(u64),
+}
+
+impl fmt::Display for BeaconError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            BeaconError::BeforeGenesis(t) => write!(f, "timestamp {} is before genesis", t),
+            BeaconError::InvalidSlotDuration => write!(f, "seconds per slot and slots per epoch must be nonzero"),
+            BeaconError::SlotOutOfRange(s) => write!(f, "slot {} is out of range; its epoch ends after the largest representable timestamp", s),
+            BeaconError::TimestampOutOfRange(t) => write!(f, "timestamp {} is out of range; its epoch ends after the largest representable timestamp", t),
+        }
+    }
+}
+
+impl error::Error for BeaconError {}
+
+struct ChainSpec {
+    genesis_time: u64,
+    seconds_per_slot: u64,
+    slots_per_epoch: u64,
+}
+
+impl ChainSpec {
+    fn from_matches(matches: &ArgMatches) -> util::Res<ChainSpec> {
+        let spec = ChainSpec {
+            genesis_time: matches.value_of("genesis-time").unwrap().parse()?,
+            seconds_per_slot: matches.value_of("seconds-per-slot").unwrap().parse()?,
+            slots_per_epoch: matches.value_of("slots-per-epoch").unwrap().parse()?,
+        };
+        if spec.seconds_per_slot == 0 || spec.slots_per_epoch == 0 {
+            return Err(BeaconError::InvalidSlotDuration.into());
+        }
+        Ok(spec)
+    }
+
+    fn slot_time(&self, slot: u64) -> Result<u64, BeaconError> {
+        slot.checked_mul(self.seconds_per_slot)
+            .and_then(|t| t.checked_add(self.genesis_time))
+            .ok_or(BeaconError::SlotOutOfRange(slot))
+    }
+
+    fn describe(&self, slot: u64) -> Result<String, BeaconError> {
+        let epoch = slot / self.slots_per_epoch;
+        let first = epoch * self.slots_per_epoch;
+        let last = first.checked_add(self.slots_per_epoch - 1).ok_or(BeaconError::SlotOutOfRange(slot))?;
+        // The epoch's last slot is the latest time described, so checking it
+        // first reports the slot the user asked about.
+        let last_time = self.slot_time(last).map_err(|_| BeaconError::SlotOutOfRange(slot))?;
+        Ok(format!("Slot: {}\nTime: {}\nEpoch: {}\nEpoch start: slot {} at {}\nEpoch end: slot {} at {}",
+                   slot, format_time(self.slot_time(slot)?), epoch,
+                   first, format_time(self.slot_time(first)?),
+                   last, format_time(last_time)))
+    }
+}
+
+/// Formats a unix timestamp as `<seconds> (<RFC 3339 UTC>)`.
+fn format_time(ts: u64) -> String {
+    format!("{} ({})", ts, util::format_utc(ts))
+}
+
+fn with_spec_args<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.arg(Arg::with_name("genesis-time")
+            .long("genesis-time")
+            .takes_value(true)
+            .default_value(MAINNET_GENESIS_TIME)
+            .help("The beacon chain genesis time as a unix timestamp."))
+        .arg(Arg::with_name("seconds-per-slot")
+            .long("seconds-per-slot")
+            .takes_value(true)
+            .default_value(MAINNET_SECONDS_PER_SLOT)
+            .help("The slot duration in seconds."))
+        .arg(Arg::with_name("slots-per-epoch")
+            .long("slots-per-epoch")
+            .takes_value(true)
+            .default_value(MAINNET_SLOTS_PER_EPOCH)
+            .help("The number of slots in an epoch."))
+}
+
+pub fn make_beacon_cmd<'a, 'b>() -> App<'a, 'b> {
+    let slot_time_cmd = with_spec_args(SubCommand::with_name("slot-time"))
+        .arg(Arg::with_name("slot")
+            .help("the slot to convert")
+            .index(1)
+            .required(true))
+        .about("Converts a slot into its wall-clock time and epoch");
+    let time_slot_cmd = with_spec_args(SubCommand::with_name("time-slot"))
+        .arg(Arg::with_name("timestamp")
+            .help("the unix timestamp to convert")
+            .index(1)
+            .required(true))
+        .about("Converts a unix timestamp into the slot and epoch containing it");
+
+    SubCommand::with_name("beacon")
+        .subcommand(slot_time_cmd)
+        .subcommand(time_slot_cmd)
+        .about("Convert between beacon chain slots, epochs, and wall-clock time.")
+}
+
+pub fn execute_beacon_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("slot-time", Some(sub)) => execute_slot_time_cmd(sub.value_of("slot").unwrap(), &ChainSpec::from_matches(sub)?),
+        ("time-slot", Some(sub)) => execute_time_slot_cmd(sub.value_of("timestamp").unwrap(), &ChainSpec::from_matches(sub)?),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_slot_time_cmd(slot: &str, spec: &ChainSpec) -> util::Res<String> {
+    let slot: u64 = slot.parse()?;
+    Ok(spec.describe(slot)?)
+}
+
+fn execute_time_slot_cmd(timestamp: &str, spec: &ChainSpec) -> util::Res<String> {
+    let ts: u64 = timestamp.parse()?;
+    if ts < spec.genesis_time {
+        return Err(BeaconError::BeforeGenesis(ts).into());
+    }
+
+    let slot = (ts - spec.genesis_time) / spec.seconds_per_slot;
+    Ok(spec.describe(slot).map_err(|_| BeaconError::TimestampOutOfRange(ts))?)
+}
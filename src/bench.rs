@@ -0,0 +1,137 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::CmdError;
+use crypto::sha3::Sha3;
+use crypto::digest::Digest;
+use secp256k1::{Secp256k1, SecretKey, Message};
+use rand::rngs::OsRng;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+enum Benchmark {
+    Keccak,
+    Sign,
+    Generate,
+}
+
+impl Benchmark {
+    fn rate_unit(&self) -> &str {
+        match self {
+            Benchmark::Keccak => "hashes/sec",
+            Benchmark::Sign => "signatures/sec",
+            Benchmark::Generate => "keys/sec",
+        }
+    }
+
+    fn run(&self, stop: &AtomicBool) -> u64 {
+        match self {
+            Benchmark::Keccak => run_keccak(stop),
+            Benchmark::Sign => run_sign(stop),
+            Benchmark::Generate => run_generate(stop),
+        }
+    }
+}
+
+fn run_keccak(stop: &AtomicBool) -> u64 {
+    let mut h = Sha3::keccak256();
+    let mut buf: [u8; 32] = [0; 32];
+    let mut count = 0;
+    while !stop.load(Ordering::Relaxed) {
+        h.input(&buf);
+        h.result(&mut buf);
+        h.reset();
+        count += 1;
+    }
+    count
+}
+
+fn run_sign(stop: &AtomicBool) -> u64 {
+    let secp = Secp256k1::new();
+    let mut rng = OsRng::new().expect("OsRng");
+    let sk = SecretKey::new(&mut rng);
+    let mut h = Sha3::keccak256();
+    let mut digest: [u8; 32] = [0; 32];
+    let mut count = 0;
+    while !stop.load(Ordering::Relaxed) {
+        h.input(&digest);
+        h.result(&mut digest);
+        h.reset();
+        let msg = Message::from_slice(&digest).expect("32 bytes");
+        secp.sign_recoverable(&msg, &sk);
+        count += 1;
+    }
+    count
+}
+
+fn run_generate(stop: &AtomicBool) -> u64 {
+    let secp = Secp256k1::new();
+    let mut rng = OsRng::new().expect("OsRng");
+    let mut h = Sha3::keccak256();
+    let mut out: [u8; 32] = [0; 32];
+    let mut count = 0;
+    while !stop.load(Ordering::Relaxed) {
+        let (_, pub_k) = secp.generate_keypair(&mut rng);
+        h.input(&pub_k.serialize_uncompressed()[1..]);
+        h.result(&mut out);
+        h.reset();
+        count += 1;
+    }
+    count
+}
+
+fn make_bench_subcmd<'a, 'b>(name: &'a str, about: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(Arg::with_name("duration")
+            .short("-d")
+            .takes_value(true)
+            .default_value("5")
+            .help("How long to run the benchmark, in seconds."))
+        .arg(Arg::with_name("threads")
+            .short("-t")
+            .takes_value(true)
+            .help("Number of worker threads. Defaults to the number of available CPUs."))
+        .about(about)
+}
+
+pub fn make_bench_cmd<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("bench")
+        .subcommand(make_bench_subcmd("keccak", "Measures keccak256 hashing throughput"))
+        .subcommand(make_bench_subcmd("sign", "Measures ECDSA signing throughput"))
+        .subcommand(make_bench_subcmd("generate", "Measures keypair and address generation throughput"))
+        .about("Measure hashing, signing, and key generation throughput.")
+}
+
+pub fn execute_bench_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let (bench, sub) = match matches.subcommand() {
+        ("keccak", Some(sub)) => (Benchmark::Keccak, sub),
+        ("sign", Some(sub)) => (Benchmark::Sign, sub),
+        ("generate", Some(sub)) => (Benchmark::Generate, sub),
+        (c, _) => return Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    };
+
+    execute_benchmark(bench, sub.value_of("duration").unwrap(), sub.value_of("threads"))
+}
+
+fn execute_benchmark(bench: Benchmark, duration: &str, threads: Option<&str>) -> util::Res<String> {
+    let secs: u64 = duration.parse()?;
+    let threads = util::parse_thread_count(threads)?;
+
+    let bench = Arc::new(bench);
+    let stop = Arc::new(AtomicBool::new(false));
+    let start = Instant::now();
+    let handles: Vec<thread::JoinHandle<u64>> = (0..threads).map(|_| {
+        let bench = Arc::clone(&bench);
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || bench.run(&stop))
+    }).collect();
+
+    thread::sleep(Duration::from_secs(secs));
+    stop.store(true, Ordering::Relaxed);
+    let total: u64 = handles.into_iter().map(|h| h.join().unwrap_or(0)).sum();
+    let elapsed = start.elapsed().as_secs_f64();
+
+    Ok(format!("{:.0} {} ({} ops in {:.2}s across {} threads)",
+               total as f64 / elapsed, bench.rate_unit(), total, elapsed, threads))
+}
@@ -0,0 +1,178 @@
+use clap::{App, SubCommand, Arg, ArgGroup, ArgMatches};
+use crate::util;
+use crate::util::{make_input_arg, read_hex_input, decode_hex, encode_hex, CmdError};
+use blst::BLST_ERROR;
+use blst::min_pk::{SecretKey, PublicKey, Signature, AggregatePublicKey, AggregateSignature};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::{error, fmt};
+
+/// The IETF ciphersuite the beacon chain uses: minimal-pubkey-size keys in G1,
+/// signatures in G2, with proof-of-possession.
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+const MIN_IKM_SIZE: usize = 32;
+
+#[derive(Debug)]
+pub enum BlsError {
+    InvalidIkmLength(usize),
+    InvalidSecretKey,
+    InvalidPublicKey(String),
+    InvalidSignature(String),
+    InvalidSignatureForMessage,
+}
+
+impl fmt::Display for BlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            BlsError::InvalidIkmLength(len) => write!(f, "key material must be at least {} bytes, but got {}", MIN_IKM_SIZE, len),
+            BlsError::InvalidSecretKey => write!(f, "invalid secret key; expected a nonzero 32-byte scalar below the curve order"),
+            BlsError::InvalidPublicKey(k) => write!(f, "{} is not a valid 48-byte compressed G1 public key", k),
+            BlsError::InvalidSignature(s) => write!(f, "{} is not a valid 96-byte compressed G2 signature", s),
+            BlsError::InvalidSignatureForMessage => write!(f, "invalid signature for the given message and public keys"),
+        }
+    }
+}
+
+impl error::Error for BlsError {}
+
+pub fn make_bls_cmd<'a, 'b>() -> App<'a, 'b> {
+    let keygen_cmd = SubCommand::with_name("keygen")
+        .arg(Arg::with_name("ikm")
+            .long("ikm")
+            .takes_value(true)
+            .help("Hex-encoded input key material of at least 32 bytes. Defaults to 32 random bytes."))
+        .about("Derives a secret key with the IETF KeyGen procedure and prints it with its public key");
+    let sign_cmd = SubCommand::with_name("sign")
+        .arg(make_input_arg("The hex-encoded message to sign, e.g. a signing root. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("private-key")
+            .short("-p")
+            .long("private-key")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded 32-byte secret key to sign with."))
+        .about("Signs a message, producing a 96-byte G2 signature");
+    let verify_cmd = SubCommand::with_name("verify")
+        .arg(make_input_arg("The hex-encoded message that was signed. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("public-key")
+            .long("public-key")
+            .required(true)
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("A hex-encoded 48-byte public key. Repeat to check an aggregate signature over the same message."))
+        .arg(Arg::with_name("sig")
+            .long("sig")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded 96-byte signature to check."))
+        .about("Verifies a signature, or a same-message aggregate signature, against its public keys");
+    let aggregate_cmd = SubCommand::with_name("aggregate")
+        .arg(Arg::with_name("sig")
+            .long("sig")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("A hex-encoded signature to aggregate. Repeat for each signature."))
+        .arg(Arg::with_name("public-key")
+            .long("public-key")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("A hex-encoded public key to aggregate. Repeat for each key."))
+        .group(ArgGroup::with_name("points")
+            .args(&["sig", "public-key"])
+            .required(true))
+        .about("Aggregates signatures or public keys into one");
+
+    SubCommand::with_name("bls")
+        .subcommand(keygen_cmd)
+        .subcommand(sign_cmd)
+        .subcommand(verify_cmd)
+        .subcommand(aggregate_cmd)
+        .about("Generate BLS12-381 keys and sign, verify, and aggregate beacon chain signatures.")
+}
+
+pub fn execute_bls_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("keygen", Some(sub)) => execute_keygen_cmd(sub.value_of("ikm")),
+        ("sign", Some(sub)) => execute_sign_cmd(sub.value_of("input").unwrap(), sub.value_of("private-key").unwrap()),
+        ("verify", Some(sub)) => execute_verify_cmd(sub.value_of("input").unwrap(), sub.values_of("public-key").unwrap().collect(), sub.value_of("sig").unwrap()),
+        ("aggregate", Some(sub)) => execute_aggregate_cmd(sub),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_keygen_cmd(ikm_hex: Option<&str>) -> util::Res<String> {
+    let ikm = match ikm_hex {
+        Some(ikm_hex) => decode_hex(ikm_hex)?,
+        None => {
+            let mut ikm = vec![0; MIN_IKM_SIZE];
+            OsRng::new()?.fill_bytes(&mut ikm);
+            ikm
+        }
+    };
+    if ikm.len() < MIN_IKM_SIZE {
+        return Err(BlsError::InvalidIkmLength(ikm.len()).into());
+    }
+
+    let sk = SecretKey::key_gen(&ikm, &[]).map_err(|_| BlsError::InvalidIkmLength(ikm.len()))?;
+    Ok(format!("{} {}", encode_hex(&sk.to_bytes()), encode_hex(&sk.sk_to_pk().to_bytes())))
+}
+
+fn execute_sign_cmd(input: &str, sk_hex: &str) -> util::Res<String> {
+    let msg = read_hex_input(input)?;
+    let sk = parse_secret_key(sk_hex)?;
+    Ok(encode_hex(&sk.sign(&msg, BLS_DST, &[]).to_bytes()))
+}
+
+fn execute_verify_cmd(input: &str, pk_hexes: Vec<&str>, sig_hex: &str) -> util::Res<String> {
+    let msg = read_hex_input(input)?;
+    let pks = pk_hexes.into_iter().map(parse_public_key).collect::<Result<Vec<_>, _>>()?;
+    let sig = parse_signature(sig_hex)?;
+
+    let pk_refs: Vec<&PublicKey> = pks.iter().collect();
+    let res = match pk_refs.as_slice() {
+        [pk] => sig.verify(true, &msg, BLS_DST, &[], pk, false),
+        _ => sig.fast_aggregate_verify(true, &msg, BLS_DST, &pk_refs),
+    };
+    if res != BLST_ERROR::BLST_SUCCESS {
+        return Err(BlsError::InvalidSignatureForMessage.into());
+    }
+    Ok(String::from("Valid signature"))
+}
+
+fn execute_aggregate_cmd(matches: &ArgMatches) -> util::Res<String> {
+    if let Some(sig_hexes) = matches.values_of("sig") {
+        let sigs = sig_hexes.map(parse_signature).collect::<Result<Vec<_>, _>>()?;
+        let sig_refs: Vec<&Signature> = sigs.iter().collect();
+        let agg = AggregateSignature::aggregate(&sig_refs, false)
+            .map_err(|_| CmdError::InvalidInput(String::from("no signatures to aggregate")))?;
+        return Ok(encode_hex(&agg.to_signature().to_bytes()));
+    }
+
+    let pks = matches.values_of("public-key").unwrap().map(parse_public_key).collect::<Result<Vec<_>, _>>()?;
+    let pk_refs: Vec<&PublicKey> = pks.iter().collect();
+    let agg = AggregatePublicKey::aggregate(&pk_refs, false)
+        .map_err(|_| CmdError::InvalidInput(String::from("no public keys to aggregate")))?;
+    Ok(encode_hex(&agg.to_public_key().to_bytes()))
+}
+
+fn parse_secret_key(sk_hex: &str) -> Result<SecretKey, BlsError> {
+    decode_hex(sk_hex).ok()
+        .and_then(|b| SecretKey::from_bytes(&b).ok())
+        .ok_or(BlsError::InvalidSecretKey)
+}
+
+/// Parses a compressed public key, rejecting the identity and points outside the G1 subgroup.
+fn parse_public_key(pk_hex: &str) -> Result<PublicKey, BlsError> {
+    decode_hex(pk_hex).ok()
+        .and_then(|b| PublicKey::key_validate(&b).ok())
+        .ok_or_else(|| BlsError::InvalidPublicKey(String::from(pk_hex)))
+}
+
+/// Parses a compressed signature, rejecting points outside the G2 subgroup.
+fn parse_signature(sig_hex: &str) -> Result<Signature, BlsError> {
+    decode_hex(sig_hex).ok()
+        .and_then(|b| Signature::sig_validate(&b, false).ok())
+        .ok_or_else(|| BlsError::InvalidSignature(String::from(sig_hex)))
+}
@@ -0,0 +1,168 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::CmdError;
+use serde_json::Value;
+use std::{error, fmt, fs};
+
+const CHAINLIST_URL: &str = "https://chainid.network/chains.json";
+const CHAINLIST_CACHE: &str = "chains.json";
+const EMBEDDED_CHAINS: &str = include_str!("chains.json");
+
+#[derive(Debug)]
+pub enum ChainError {
+    UnknownChain(String),
+    InvalidRegistry(String),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            ChainError::UnknownChain(c) => write!(f, "no chain found matching {}", c),
+            ChainError::InvalidRegistry(e) => write!(f, "invalid chain registry: {}", e),
+        }
+    }
+}
+
+impl error::Error for ChainError {}
+
+pub struct Chain {
+    pub chain_id: u64,
+    pub name: String,
+    pub short_name: String,
+    pub currency_name: String,
+    pub currency_symbol: String,
+    pub currency_decimals: u64,
+    pub rpc: Vec<String>,
+    pub explorers: Vec<(String, String)>,
+}
+
+impl Chain {
+    fn from_json(v: &Value) -> Option<Chain> {
+        let currency = &v["nativeCurrency"];
+        let rpc = v["rpc"].as_array()
+            .map(|urls| urls.iter().filter_map(|u| u.as_str()).map(String::from).collect())
+            .unwrap_or_default();
+        let explorers = v["explorers"].as_array()
+            .map(|exps| exps.iter().filter_map(|e| {
+                Some((String::from(e["name"].as_str()?), String::from(e["url"].as_str()?)))
+            }).collect())
+            .unwrap_or_default();
+
+        Some(Chain {
+            chain_id: v["chainId"].as_u64()?,
+            name: String::from(v["name"].as_str()?),
+            short_name: String::from(v["shortName"].as_str().unwrap_or("")),
+            currency_name: String::from(currency["name"].as_str().unwrap_or("")),
+            currency_symbol: String::from(currency["symbol"].as_str().unwrap_or("")),
+            currency_decimals: currency["decimals"].as_u64().unwrap_or(18),
+            rpc,
+            explorers,
+        })
+    }
+
+    fn matches(&self, query: &str) -> bool {
+        match parse_chain_id(query) {
+            Some(id) => self.chain_id == id,
+            None => self.name.eq_ignore_ascii_case(query) || self.short_name.eq_ignore_ascii_case(query),
+        }
+    }
+}
+
+impl fmt::Display for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Name: {}", self.name)?;
+        writeln!(f, "Chain ID: {}", self.chain_id)?;
+        writeln!(f, "Short name: {}", self.short_name)?;
+        write!(f, "Native currency: {} ({}, {} decimals)", self.currency_name, self.currency_symbol, self.currency_decimals)?;
+        if !self.rpc.is_empty() {
+            write!(f, "\nRPC URLs:")?;
+            for url in &self.rpc {
+                write!(f, "\n  {}", url)?;
+            }
+        }
+        if !self.explorers.is_empty() {
+            write!(f, "\nExplorers:")?;
+            for (name, url) in &self.explorers {
+                write!(f, "\n  {}: {}", name, url)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_chain_id(s: &str) -> Option<u64> {
+    if s.starts_with("0x") {
+        u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+fn parse_registry(data: &str) -> Result<Vec<Chain>, ChainError> {
+    let v: Value = serde_json::from_str(data).map_err(|e| ChainError::InvalidRegistry(e.to_string()))?;
+    match v.as_array() {
+        Some(chains) => Ok(chains.iter().filter_map(Chain::from_json).collect()),
+        None => Err(ChainError::InvalidRegistry(String::from("expected a JSON array of chains"))),
+    }
+}
+
+/// Loads the chain registry, preferring a previously refreshed copy over the embedded dataset.
+pub fn load_chains() -> util::Res<Vec<Chain>> {
+    let cache = util::data_path(CHAINLIST_CACHE)?;
+    if let Ok(data) = fs::read_to_string(&cache) {
+        return Ok(parse_registry(&data)?);
+    }
+
+    Ok(parse_registry(EMBEDDED_CHAINS)?)
+}
+
+fn refresh_chains() -> util::Res<()> {
+    let v = util::http_get_json(CHAINLIST_URL)?;
+    let data = v.to_string();
+    parse_registry(&data)?;
+
+    let cache = util::data_path(CHAINLIST_CACHE)?;
+    if let Some(dir) = cache.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(cache, data)?;
+    Ok(())
+}
+
+pub fn find_chain(query: &str) -> util::Res<Chain> {
+    load_chains()?
+        .into_iter()
+        .find(|c| c.matches(query))
+        .ok_or_else(|| ChainError::UnknownChain(String::from(query)).into())
+}
+
+pub fn make_chain_cmd<'a, 'b>() -> App<'a, 'b> {
+    let info_cmd = SubCommand::with_name("info")
+        .arg(Arg::with_name("chain")
+            .help("a chain ID (decimal or hex), name, or short name")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("refresh")
+            .long("refresh")
+            .help("Downloads the latest chain registry from chainid.network before the lookup."))
+        .about("Prints the name, native currency, RPC URLs, and explorers of a chain");
+
+    SubCommand::with_name("chain")
+        .subcommand(info_cmd)
+        .about("Look up information about EVM chains.")
+}
+
+pub fn execute_chain_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("info", Some(sub)) => execute_info_cmd(sub.value_of("chain").unwrap(), sub.is_present("refresh")),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_info_cmd(query: &str, refresh: bool) -> util::Res<String> {
+    if refresh {
+        refresh_chains()?;
+    }
+
+    Ok(find_chain(query)?.to_string())
+}
@@ -1,26 +1,94 @@
 use clap::{App, SubCommand, ArgMatches, Arg};
 use crypto::sha3::Sha3;
 use crypto::digest::Digest;
-use crate::util::{make_input_arg, read_hex_input, decode_hex, encode_hex, CmdError};
-use secp256k1::{Secp256k1, SecretKey, Message};
+use crate::util::{make_input_arg, read_hex_input, read_raw_input, decode_hex, encode_hex, CmdError};
+use secp256k1::{Secp256k1, SecretKey, PublicKey, Message};
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
 use std::io::Write;
-use crypto::sha2::Sha256;
-use std::{error, fmt};
+use crypto::sha2::{Sha256, Sha512};
+use crypto::hmac::Hmac;
+use crypto::mac::{Mac, MacResult};
+use crypto::aes::{self, KeySize};
+use crypto::{pbkdf2, scrypt};
+use crypto::scrypt::ScryptParams;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::{error, fmt, fs, io};
+use std::fs::File;
+use std::io::Read;
 use crate::util;
+use serde_json::json;
+use num_bigint::BigUint;
+use num_traits::Zero;
 use crypto::ripemd160::Ripemd160;
+use crypto::blake2b::Blake2b;
+use crypto::blake2s::Blake2s;
+use c_kzg::{ethereum_kzg_settings, Blob, BYTES_PER_BLOB};
+
+/// Files and raw stdin are hashed in chunks of this size.
+const HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+const ECIES_IV_SIZE: usize = 16;
+const BIP32_HARDENED: u32 = 1 << 31;
+const BIP39_ENGLISH: &str = include_str!("bip39_english.txt");
+const SECP256K1_ORDER: &str = "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+const KZG_COMMITMENT_SIZE: usize = 48;
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+/// The scrypt parameters geth uses for "standard" V3 keystores.
+const KEYSTORE_SCRYPT_N: u64 = 1 << 18;
+const KEYSTORE_SCRYPT_R: u32 = 8;
+const KEYSTORE_SCRYPT_P: u32 = 1;
+/// The most memory scrypt's 128 * r * n byte scratch buffer may use.
+const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
 
 #[derive(Debug)]
 pub enum CryptoCmdError {
     InvalidSignatureLength,
+    InvalidSignature,
+    InvalidSignatureComponent(&'static str, String),
+    SignerMismatch(String, String),
     InvalidInputLength(usize, usize),
     InvalidPrivateKey,
+    InvalidPrivateKeyLength(usize),
+    PrivateKeyOutOfRange,
+    InvalidPublicKey,
+    InvalidPublicKeyPrefix(u8, usize),
+    InvalidPublicKeyLength(usize),
+    PointNotOnCurve,
+    InvalidTweak,
+    InvalidDigestLength(usize, usize),
+    InvalidCiphertext,
+    InvalidKdfParams(String),
+    InvalidLeaf(String),
+    InvalidDerivationPath(String),
+    InvalidMnemonic(String),
+    InvalidBlob,
+    EmptyTree,
 }
 
 impl fmt::Display for CryptoCmdError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
             CryptoCmdError::InvalidSignatureLength => write!(f, "invalid signature length"),
-            CryptoCmdError::InvalidPrivateKey => write!(f, "invalid private key"),
+            CryptoCmdError::InvalidSignature => write!(f, "invalid signature; no signer could be recovered"),
+            CryptoCmdError::InvalidSignatureComponent(name, reason) => write!(f, "invalid signature component {}: {}", name, reason),
+            CryptoCmdError::SignerMismatch(expected, recovered) => write!(f, "signature mismatch: expected signer {} but recovered {}", expected, recovered),
+            CryptoCmdError::InvalidPrivateKey => write!(f, "invalid private key; it is not hex-encoded"),
+            CryptoCmdError::InvalidPrivateKeyLength(len) => write!(f, "invalid private key length, expected 32 bytes but got {}", len),
+            CryptoCmdError::PrivateKeyOutOfRange => write!(f, "invalid private key; it must be nonzero and less than the secp256k1 curve order"),
+            CryptoCmdError::InvalidPublicKey => write!(f, "invalid public key"),
+            CryptoCmdError::InvalidPublicKeyPrefix(prefix, len) => write!(f, "invalid prefix 0x{:02x} for a {}-byte public key; expected {}", prefix, len, if *len == 33 { "0x02 or 0x03" } else { "0x04" }),
+            CryptoCmdError::PointNotOnCurve => write!(f, "invalid public key; the point is not on the secp256k1 curve"),
+            CryptoCmdError::InvalidPublicKeyLength(len) => write!(f, "invalid public key length {}; expected 33 (compressed), 64 (raw), or 65 (uncompressed) bytes", len),
+            CryptoCmdError::InvalidDigestLength(len, max) => write!(f, "invalid digest length {}; must be between 1 and {} bytes", len, max),
+            CryptoCmdError::InvalidCiphertext => write!(f, "invalid ciphertext; it is malformed, was encrypted to a different key, or was tampered with"),
+            CryptoCmdError::InvalidKdfParams(s) => write!(f, "invalid KDF parameters: {}", s),
+            CryptoCmdError::InvalidLeaf(s) => write!(f, "invalid Merkle leaf {}; leaves must be 32-byte hex values", s),
+            CryptoCmdError::InvalidDerivationPath(p) => write!(f, "invalid derivation path {}", p),
+            CryptoCmdError::InvalidMnemonic(s) => write!(f, "invalid mnemonic: {}", s),
+            CryptoCmdError::InvalidBlob => write!(f, "invalid blob; every 32-byte field element must be below the BLS12-381 scalar field modulus"),
+            CryptoCmdError::EmptyTree => write!(f, "a Merkle tree needs at least one leaf"),
+            CryptoCmdError::InvalidTweak => write!(f, "invalid tweak; must be a 32-byte scalar within the curve order"),
             CryptoCmdError::InvalidInputLength(exp_len, recv_len) => write!(f, "invalid input length, expected {} but got {}", exp_len, recv_len),
         }
     }
@@ -28,110 +96,1268 @@ impl fmt::Display for CryptoCmdError {
 
 impl error::Error for CryptoCmdError {}
 
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3::keccak256();
+    hasher.input(data);
+    let mut out: [u8; 32] = [0; 32];
+    hasher.result(&mut out);
+    out
+}
+
+pub fn parse_private_key(pk_hex: &str) -> Result<SecretKey, CryptoCmdError> {
+    let pk_buf = decode_hex(pk_hex).map_err(|_| CryptoCmdError::InvalidPrivateKey)?;
+    if pk_buf.len() != 32 {
+        return Err(CryptoCmdError::InvalidPrivateKeyLength(pk_buf.len()));
+    }
+    SecretKey::from_slice(pk_buf.as_slice()).map_err(|_| CryptoCmdError::PrivateKeyOutOfRange)
+}
+
+pub fn parse_public_key(pub_hex: &str) -> Result<PublicKey, CryptoCmdError> {
+    let pub_buf = decode_hex(pub_hex).map_err(|_| CryptoCmdError::InvalidPublicKey)?;
+    PublicKey::from_slice(pub_buf.as_slice()).map_err(|_| CryptoCmdError::InvalidPublicKey)
+}
+
+/// Derives the Ethereum address of a public key.
+pub fn public_key_to_address(pub_k: &PublicKey) -> [u8; 20] {
+    let hash = keccak256(&pub_k.serialize_uncompressed()[1..]);
+    let mut out = [0; 20];
+    out.copy_from_slice(&hash[12..]);
+    out
+}
+
+/// Recovers the public key that produced a 65-byte `r || s || v` signature
+/// over a 32-byte hash. `v` may be given as 0/1 or 27/28.
+pub fn recover_public_key(hash: &[u8], sig: &[u8]) -> util::Res<PublicKey> {
+    if hash.len() != 32 {
+        return Err(CryptoCmdError::InvalidInputLength(32, hash.len()).into());
+    }
+    if sig.len() != 65 {
+        return Err(CryptoCmdError::InvalidSignatureLength.into());
+    }
+
+    let v = if sig[64] >= 27 { sig[64] - 27 } else { sig[64] };
+    let rec_id = RecoveryId::from_i32(v as i32).map_err(|_| CryptoCmdError::InvalidSignature)?;
+    let rec_sig = RecoverableSignature::from_compact(&sig[..64], rec_id).map_err(|_| CryptoCmdError::InvalidSignature)?;
+    let msg = Message::from_slice(hash)?;
+    Ok(Secp256k1::new().recover(&msg, &rec_sig).map_err(|_| CryptoCmdError::InvalidSignature)?)
+}
+
+/// Parses a SEC1-encoded public key, or a raw 64-byte `x || y` point,
+/// checking the prefix byte before validating that the point is on the curve.
+pub fn parse_sec1_public_key(pub_hex: &str) -> Result<PublicKey, CryptoCmdError> {
+    let mut buf = decode_hex(pub_hex).map_err(|_| CryptoCmdError::InvalidPublicKey)?;
+    match (buf.len(), buf.first()) {
+        (33, Some(0x02)) | (33, Some(0x03)) | (65, Some(0x04)) => {}
+        (64, _) => buf.insert(0, 0x04),
+        (33, Some(p)) | (65, Some(p)) => return Err(CryptoCmdError::InvalidPublicKeyPrefix(*p, buf.len())),
+        (len, _) => return Err(CryptoCmdError::InvalidPublicKeyLength(len)),
+    }
+    PublicKey::from_slice(&buf).map_err(|_| CryptoCmdError::PointNotOnCurve)
+}
+
+fn make_hash_cmd<'a, 'b>(name: &'a str, about: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(Arg::with_name("input")
+            .short("-i")
+            .takes_value(true)
+            .required_unless("file")
+            .conflicts_with("file")
+            .multiple(true)
+            .number_of_values(1)
+            .help("The input to hash. Hex-encoded unless --raw is set. If - is provided, will read from stdin. Repeat to hash the concatenation of several inputs."))
+        .arg(Arg::with_name("raw")
+            .long("raw")
+            .visible_alias("string")
+            .help("Hashes the input as plain UTF-8 text rather than hex."))
+        .arg(Arg::with_name("file")
+            .long("file")
+            .takes_value(true)
+            .help("Hashes the raw contents of a file, streaming it in constant memory."))
+        .arg(Arg::with_name("lines")
+            .long("lines")
+            .help("Hashes each line of the input separately, printing one digest per line."))
+        .about(about)
+}
+
+fn with_digest_length_arg<'a, 'b>(cmd: App<'a, 'b>, default: &'a str) -> App<'a, 'b> {
+    cmd.arg(Arg::with_name("length")
+        .short("-l")
+        .long("length")
+        .takes_value(true)
+        .default_value(default)
+        .help("The digest length in bytes."))
+}
+
+fn parse_digest_length(matches: &ArgMatches, max: usize) -> util::Res<usize> {
+    let len: usize = matches.value_of("length").unwrap().parse()?;
+    if len == 0 || len > max {
+        return Err(CryptoCmdError::InvalidDigestLength(len, max).into());
+    }
+    Ok(len)
+}
+
+/// Reads the inputs to hash, one per line when `--lines` is set and otherwise
+/// a single input.
+fn read_hash_inputs(matches: &ArgMatches) -> util::Res<Vec<Vec<u8>>> {
+    if !matches.is_present("lines") {
+        return Ok(vec![read_hash_input(matches)?]);
+    }
+
+    let data = match (matches.value_of("file"), hash_input_values(matches).as_slice()) {
+        (Some(path), _) => fs::read(path)?,
+        (None, [input]) => read_raw_input(input)?,
+        _ => return Err(CmdError::InvalidArgument(String::from("--lines takes a single input")).into()),
+    };
+    let text = String::from_utf8(data).map_err(|_| CmdError::InvalidInput(String::from("lines must be UTF-8 text")))?;
+    let raw = matches.is_present("raw");
+    text.lines()
+        .map(|l| l.strip_suffix('\r').unwrap_or(l))
+        .enumerate()
+        .map(|(i, l)| if raw {
+            Ok(l.as_bytes().to_vec())
+        } else {
+            decode_hex(l.trim()).map_err(|_| CmdError::InvalidInput(format!("line {} is not hex-encoded", i + 1)).into())
+        })
+        .collect()
+}
+
+/// Reads the data to hash according to the `--file` and `--raw` flags.
+fn read_hash_input(matches: &ArgMatches) -> util::Res<Vec<u8>> {
+    if let Some(path) = matches.value_of("file") {
+        return Ok(fs::read(path)?);
+    }
+
+    let mut buf = Vec::new();
+    for input in hash_input_values(matches) {
+        if matches.is_present("raw") {
+            buf.extend(read_raw_input(input)?);
+        } else {
+            buf.extend(read_hex_input(input)?);
+        }
+    }
+    Ok(buf)
+}
+
+fn hash_input_values<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    matches.values_of("input").map(|v| v.collect()).unwrap_or_default()
+}
+
+fn make_shared_mac_data_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("shared-mac-data")
+        .long("shared-mac-data")
+        .takes_value(true)
+        .help("Hex-encoded data authenticated by the MAC but not encrypted, such as the EIP-8 size prefix.")
+}
+
+fn make_kdf_cmd<'a, 'b>(name: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(make_input_arg("The password, as plain text. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("salt")
+            .long("salt")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded salt."))
+        .arg(Arg::with_name("dklen")
+            .long("dklen")
+            .takes_value(true)
+            .default_value("32")
+            .help("The length of the derived key in bytes."))
+}
+
+fn make_merkle_cmd<'a, 'b>(name: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(make_input_arg("A file with one hex-encoded 32-byte leaf per line. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("hash-leaves")
+            .long("hash-leaves")
+            .help("Hashes each leaf with keccak256 before building the tree, allowing leaves of any length."))
+}
+
+fn make_tweak_cmd<'a, 'b>(name: &'a str, about: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(Arg::with_name("private-key")
+            .short("-k")
+            .takes_value(true)
+            .required_unless("public-key")
+            .conflicts_with("public-key")
+            .help("A hex-encoded private key to tweak."))
+        .arg(Arg::with_name("public-key")
+            .short("-p")
+            .takes_value(true)
+            .help("A hex-encoded compressed or uncompressed public key to tweak."))
+        .arg(Arg::with_name("tweak")
+            .short("-t")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded 32-byte scalar to tweak the key by."))
+        .about(about)
+}
+
 pub fn make_crypto_cmd<'a, 'b>() -> App<'a, 'b> {
-    let keccak_256_cmd = SubCommand::with_name("keccak256")
-        .arg(make_input_arg("The input to hash"))
-        .about("Generates the keccak256 hash of the input");
-    let sha2_256_cmd = SubCommand::with_name("sha2-256")
-        .arg(make_input_arg("The input to hash"))
-        .about("Generates the SHA2-256 hash of the input");
-    let ripemd_160_cmd = SubCommand::with_name("ripemd-160")
-        .arg(make_input_arg("The input to hash."))
-        .about("Generates the RIPEMD-160 hash of the input");
-    let eth_signed_msg_cmd = SubCommand::with_name("esmh")
-        .arg(make_input_arg("The input to hash"))
-        .about("Generates a message hash compatible with eth_sign.");
+    let keccak_256_cmd = make_hash_cmd("keccak256", "Generates the keccak256 hash of the input, as used by Ethereum");
+    let keccak_512_cmd = make_hash_cmd("keccak512", "Generates the keccak512 hash of the input, using the original Keccak padding");
+    let sha3_256_cmd = make_hash_cmd("sha3-256", "Generates the NIST SHA3-256 hash of the input. This differs from Ethereum's keccak256");
+    let blake2b_cmd = with_digest_length_arg(make_hash_cmd("blake2b", "Generates the BLAKE2b hash of the input, as used by the EIP-152 precompile"), "64");
+    let blake2s_cmd = with_digest_length_arg(make_hash_cmd("blake2s", "Generates the BLAKE2s hash of the input"), "32");
+    let hmac_cmd = make_hash_cmd("hmac", "Generates an HMAC of the input")
+        .arg(Arg::with_name("alg")
+            .long("alg")
+            .takes_value(true)
+            .possible_values(&["sha256", "sha512"])
+            .default_value("sha256")
+            .help("The hash function to use."))
+        .arg(Arg::with_name("key")
+            .long("key")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded MAC key."));
+    let sha3_512_cmd = make_hash_cmd("sha3-512", "Generates the NIST SHA3-512 hash of the input. This differs from keccak512");
+    let sha2_256_cmd = make_hash_cmd("sha2-256", "Generates the SHA2-256 hash of the input");
+    let ripemd_160_cmd = make_hash_cmd("ripemd-160", "Generates the RIPEMD-160 hash of the input");
+    let eth_signed_msg_cmd = make_hash_cmd("esmh", "Generates a message hash compatible with eth_sign.");
+    let validator_msg_cmd = make_hash_cmd("esmh-validator", "Generates an EIP-191 version 0x00 hash of data for an intended validator.")
+        .arg(Arg::with_name("validator")
+            .long("validator")
+            .required(true)
+            .takes_value(true)
+            .help("The address of the contract that will validate the signature."));
     let decompose_sig_cmd = SubCommand::with_name("decompose-sig")
         .arg(make_input_arg("The signature to decompose"))
+        .arg(Arg::with_name("json")
+            .long("json")
+            .help("Prints the components as JSON."))
         .about("Decomposes a signature into its V, R, and S components");
+    let inspect_sig_cmd = SubCommand::with_name("inspect-sig")
+        .arg(make_input_arg("The hex-encoded 65-byte signature to inspect"))
+        .about("Checks a signature for malleability and out-of-range components");
+    let expand_sig_cmd = SubCommand::with_name("expand-sig")
+        .arg(make_input_arg("The hex-encoded 64-byte EIP-2098 compact signature"))
+        .about("Expands an EIP-2098 compact signature into the 65-byte r || s || v form");
+    let compose_sig_cmd = SubCommand::with_name("compose-sig")
+        .arg(Arg::with_name("r")
+            .long("r")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded R component."))
+        .arg(Arg::with_name("s")
+            .long("s")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded S component."))
+        .arg(Arg::with_name("v")
+            .long("v")
+            .required(true)
+            .takes_value(true)
+            .help("The recovery value: 0, 1, 27, or 28."))
+        .about("Composes a 65-byte signature from its V, R, and S components");
+    let convert_v_cmd = SubCommand::with_name("convert-v")
+        .arg(Arg::with_name("v")
+            .help("the v value to convert, as 0/1, 27/28, or an EIP-155 value")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("chain-id")
+            .long("chain-id")
+            .takes_value(true)
+            .help("The chain ID used to compute the EIP-155 v value."))
+        .about("Converts a signature's v value between the 0/1, 27/28, and EIP-155 conventions");
     let sign_cmd = SubCommand::with_name("ecdsa-sign")
-        .arg(make_input_arg("The data to sign"))
+        .arg(make_input_arg("The hex-encoded 32-byte hash to sign, or the message text when --message is set"))
+        .arg(Arg::with_name("message")
+            .long("message")
+            .help("Signs the input as a plain-text message, applying the eth_sign prefix and hashing first."))
         .arg(Arg::with_name("private-key")
             .short("-k")
             .required(true)
             .takes_value(true)
             .help("A hex-encoded private key to sign with."))
         .about("Signs the provided message");
+    let verify_cmd = SubCommand::with_name("verify")
+        .arg(make_input_arg("The hex-encoded 32-byte hash that was signed"))
+        .arg(Arg::with_name("address")
+            .short("-a")
+            .required(true)
+            .takes_value(true)
+            .help("The address expected to have signed the hash."))
+        .arg(Arg::with_name("sig")
+            .long("sig")
+            .required(true)
+            .takes_value(true)
+            .help("The hex-encoded 65-byte signature."))
+        .about("Recovers a signature's signer and checks it against an address, exiting nonzero on mismatch");
+    let compress_pubkey_cmd = SubCommand::with_name("compress-pubkey")
+        .arg(make_input_arg("The hex-encoded uncompressed public key"))
+        .about("Converts a public key into its 33-byte compressed form");
+    let decompress_pubkey_cmd = SubCommand::with_name("decompress-pubkey")
+        .arg(make_input_arg("The hex-encoded compressed public key"))
+        .about("Converts a public key into its 65-byte uncompressed form");
+    let ecdh_cmd = SubCommand::with_name("ecdh")
+        .arg(Arg::with_name("private-key")
+            .short("-k")
+            .required(true)
+            .takes_value(true)
+            .help("A hex-encoded private key."))
+        .arg(Arg::with_name("pubkey")
+            .long("pubkey")
+            .required(true)
+            .takes_value(true)
+            .help("The other party's hex-encoded public key."))
+        .about("Derives an ECDH shared secret from a private key and a public key");
+    let ecies_encrypt_cmd = SubCommand::with_name("ecies-encrypt")
+        .arg(make_input_arg("The hex-encoded plaintext. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("pubkey")
+            .long("pubkey")
+            .required(true)
+            .takes_value(true)
+            .help("The recipient's hex-encoded public key."))
+        .arg(make_shared_mac_data_arg())
+        .about("Encrypts data to a public key with the ECIES scheme used by devp2p");
+    let ecies_decrypt_cmd = SubCommand::with_name("ecies-decrypt")
+        .arg(make_input_arg("The hex-encoded ciphertext. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("private-key")
+            .short("-k")
+            .required(true)
+            .takes_value(true)
+            .help("The recipient's hex-encoded private key."))
+        .arg(make_shared_mac_data_arg())
+        .about("Decrypts ECIES ciphertext produced by ecies-encrypt or a devp2p peer");
+    let scrypt_cmd = make_kdf_cmd("scrypt")
+        .arg(Arg::with_name("n")
+            .long("n")
+            .takes_value(true)
+            .default_value("262144")
+            .help("The CPU/memory cost parameter. Must be a power of two."))
+        .arg(Arg::with_name("r")
+            .long("r")
+            .takes_value(true)
+            .default_value("8")
+            .help("The block size parameter."))
+        .arg(Arg::with_name("p")
+            .long("p")
+            .takes_value(true)
+            .default_value("1")
+            .help("The parallelization parameter."))
+        .about("Derives a key from a password with scrypt");
+    let pbkdf2_cmd = make_kdf_cmd("pbkdf2")
+        .arg(Arg::with_name("iterations")
+            .short("-c")
+            .long("iterations")
+            .takes_value(true)
+            .default_value("262144")
+            .help("The number of iterations."))
+        .arg(Arg::with_name("prf")
+            .long("prf")
+            .takes_value(true)
+            .possible_values(&["sha256", "sha512"])
+            .default_value("sha256")
+            .help("The HMAC hash function to use."))
+        .about("Derives a key from a password with PBKDF2");
+    let merkle_root_cmd = make_merkle_cmd("merkle-root")
+        .about("Computes the root of a sorted-pair keccak256 Merkle tree");
+    let merkle_proof_cmd = make_merkle_cmd("merkle-proof")
+        .arg(Arg::with_name("leaf")
+            .long("leaf")
+            .takes_value(true)
+            .help("Only prints the proof for this leaf."))
+        .about("Computes OpenZeppelin MerkleProof-compatible proofs for each leaf, as JSON");
+    let kzg_cmd = SubCommand::with_name("kzg")
+        .subcommand(make_blob_cmd("commit")
+            .about("Computes the KZG commitment to a blob using the mainnet trusted setup"))
+        .subcommand(make_blob_cmd("proof")
+            .about("Computes the KZG proof that a blob matches its commitment, as carried in blob sidecars"))
+        .subcommand(SubCommand::with_name("versioned-hash")
+            .arg(make_input_arg("The hex-encoded 48-byte KZG commitment"))
+            .about("Computes the EIP-4844 versioned hash of a blob commitment"))
+        .about("EIP-4844 blob commitment tooling");
+    let ec_cmd = SubCommand::with_name("ec")
+        .subcommand(make_tweak_cmd("tweak-add", "Adds a scalar to a private key, or the scalar's point to a public key"))
+        .subcommand(make_tweak_cmd("tweak-mul", "Multiplies a private or public key by a scalar"))
+        .subcommand(SubCommand::with_name("point-add")
+            .arg(Arg::with_name("public-key")
+                .short("-p")
+                .required(true)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .min_values(2)
+                .help("A hex-encoded public key to add. Repeat for each point."))
+            .about("Adds two or more public keys as curve points"))
+        .about("Performs secp256k1 scalar and point arithmetic");
 
     SubCommand::with_name("crypto")
         .subcommand(keccak_256_cmd)
+        .subcommand(keccak_512_cmd)
+        .subcommand(sha3_256_cmd)
+        .subcommand(sha3_512_cmd)
         .subcommand(sha2_256_cmd)
         .subcommand(ripemd_160_cmd)
+        .subcommand(blake2b_cmd)
+        .subcommand(blake2s_cmd)
+        .subcommand(hmac_cmd)
         .subcommand(eth_signed_msg_cmd)
+        .subcommand(validator_msg_cmd)
         .subcommand(decompose_sig_cmd)
+        .subcommand(expand_sig_cmd)
+        .subcommand(inspect_sig_cmd)
+        .subcommand(compose_sig_cmd)
+        .subcommand(convert_v_cmd)
         .subcommand(sign_cmd)
+        .subcommand(verify_cmd)
+        .subcommand(compress_pubkey_cmd)
+        .subcommand(decompress_pubkey_cmd)
+        .subcommand(ecdh_cmd)
+        .subcommand(ecies_encrypt_cmd)
+        .subcommand(ecies_decrypt_cmd)
+        .subcommand(scrypt_cmd)
+        .subcommand(pbkdf2_cmd)
+        .subcommand(merkle_root_cmd)
+        .subcommand(merkle_proof_cmd)
+        .subcommand(kzg_cmd)
+        .subcommand(ec_cmd)
         .about("Hash, sign, and verify data.")
 }
 
 pub fn execute_crypto_cmd(matches: &ArgMatches) -> util::Res<String> {
     match matches.subcommand() {
-        ("keccak256", Some(sub)) => execute_keccak256(sub.value_of("input").unwrap()),
-        ("sha2-256", Some(sub)) => execute_sha2_256(sub.value_of("input").unwrap()),
-        ("ripemd-160", Some(sub)) => execute_ripemd_160(sub.value_of("input").unwrap()),
-        ("esmh", Some(sub)) => execute_eth_signed_msg_cmd(sub.value_of("input").unwrap()),
-        ("decompose-sig", Some(sub)) => execute_decompose_sig_cmd(sub.value_of("input").unwrap()),
-        ("ecdsa-sign", Some(sub)) => execute_sign_cmd(sub.value_of("input").unwrap(), sub.value_of("private-key").unwrap()),
+        ("keccak256", Some(sub)) => execute_hash_cmd(sub, &mut Sha3::keccak256()),
+        ("keccak512", Some(sub)) => execute_hash_cmd(sub, &mut Sha3::keccak512()),
+        ("sha3-256", Some(sub)) => execute_hash_cmd(sub, &mut Sha3::sha3_256()),
+        ("sha3-512", Some(sub)) => execute_hash_cmd(sub, &mut Sha3::sha3_512()),
+        ("sha2-256", Some(sub)) => execute_hash_cmd(sub, &mut Sha256::new()),
+        ("ripemd-160", Some(sub)) => execute_hash_cmd(sub, &mut Ripemd160::new()),
+        ("blake2b", Some(sub)) => execute_hash_cmd(sub, &mut Blake2b::new(parse_digest_length(sub, 64)?)),
+        ("blake2s", Some(sub)) => execute_hash_cmd(sub, &mut Blake2s::new(parse_digest_length(sub, 32)?)),
+        ("hmac", Some(sub)) => execute_hmac_cmd(sub),
+        ("esmh", Some(sub)) => execute_eth_signed_msg_cmd(sub),
+        ("esmh-validator", Some(sub)) => execute_validator_msg_cmd(sub),
+        ("decompose-sig", Some(sub)) => execute_decompose_sig_cmd(sub.value_of("input").unwrap(), sub.is_present("json")),
+        ("inspect-sig", Some(sub)) => execute_inspect_sig_cmd(sub.value_of("input").unwrap()),
+        ("expand-sig", Some(sub)) => execute_expand_sig_cmd(sub.value_of("input").unwrap()),
+        ("compose-sig", Some(sub)) => execute_compose_sig_cmd(sub.value_of("r").unwrap(), sub.value_of("s").unwrap(), sub.value_of("v").unwrap()),
+        ("convert-v", Some(sub)) => execute_convert_v_cmd(sub.value_of("v").unwrap(), sub.value_of("chain-id")),
+        ("ecdsa-sign", Some(sub)) => execute_sign_cmd(sub.value_of("input").unwrap(), sub.value_of("private-key").unwrap(), sub.is_present("message")),
+        ("verify", Some(sub)) => execute_verify_cmd(sub.value_of("input").unwrap(), sub.value_of("address").unwrap(), sub.value_of("sig").unwrap()),
+        ("compress-pubkey", Some(sub)) => Ok(encode_hex(&parse_sec1_public_key(sub.value_of("input").unwrap())?.serialize())),
+        ("decompress-pubkey", Some(sub)) => Ok(encode_hex(&parse_sec1_public_key(sub.value_of("input").unwrap())?.serialize_uncompressed())),
+        ("ecdh", Some(sub)) => execute_ecdh_cmd(sub.value_of("private-key").unwrap(), sub.value_of("pubkey").unwrap()),
+        ("ecies-encrypt", Some(sub)) => execute_ecies_encrypt_cmd(sub.value_of("input").unwrap(), sub.value_of("pubkey").unwrap(), sub.value_of("shared-mac-data")),
+        ("ecies-decrypt", Some(sub)) => execute_ecies_decrypt_cmd(sub.value_of("input").unwrap(), sub.value_of("private-key").unwrap(), sub.value_of("shared-mac-data")),
+        ("scrypt", Some(sub)) => execute_scrypt_cmd(sub),
+        ("pbkdf2", Some(sub)) => execute_pbkdf2_cmd(sub),
+        ("merkle-root", Some(sub)) => Ok(encode_hex(&MerkleTree::new(read_merkle_leaves(sub)?)?.root())),
+        ("merkle-proof", Some(sub)) => execute_merkle_proof_cmd(sub),
+        ("kzg", Some(sub)) => execute_kzg_cmd(sub),
+        ("ec", Some(sub)) => execute_ec_cmd(sub),
         (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
     }
 }
 
-fn execute_keccak256(input: &str) -> util::Res<String> {
-    let buf = read_hex_input(input)?;
-    let mut hasher = Sha3::keccak256();
-    hasher.input(buf.as_slice());
-    Ok(format!("0x{}", hasher.result_str()))
+fn execute_ec_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("tweak-add", Some(sub)) => execute_tweak_cmd(Tweak::Add, sub.value_of("private-key"), sub.value_of("public-key"), sub.value_of("tweak").unwrap()),
+        ("tweak-mul", Some(sub)) => execute_tweak_cmd(Tweak::Mul, sub.value_of("private-key"), sub.value_of("public-key"), sub.value_of("tweak").unwrap()),
+        ("point-add", Some(sub)) => execute_point_add_cmd(sub.values_of("public-key").unwrap().collect()),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
 }
 
-fn execute_sha2_256(input: &str) -> util::Res<String> {
-    let buf = read_hex_input(input)?;
-    let mut hasher = Sha256::new();
-    hasher.input(buf.as_slice());
+fn execute_hash_cmd(matches: &ArgMatches, hasher: &mut dyn Digest) -> util::Res<String> {
+    if matches.is_present("lines") {
+        let digests: Vec<String> = read_hash_inputs(matches)?.iter().map(|buf| {
+            hasher.reset();
+            hasher.input(buf);
+            format!("0x{}", hasher.result_str())
+        }).collect();
+        return Ok(digests.join("\n"));
+    }
+
+    if let Some(path) = matches.value_of("file") {
+        stream_into(File::open(path)?, hasher)?;
+    } else if matches.is_present("raw") && hash_input_values(matches) == ["-"] {
+        stream_into(io::stdin().lock(), hasher)?;
+    } else {
+        hasher.input(&read_hash_input(matches)?);
+    }
     Ok(format!("0x{}", hasher.result_str()))
 }
 
-fn execute_ripemd_160(input: &str) -> util::Res<String> {
-    let buf = read_hex_input(input)?;
-    let mut hasher = Ripemd160::new();
-    hasher.input(buf.as_slice());
-    Ok(format!("0x{}", hasher.result_str()))
+/// Feeds a reader into a hasher in fixed-size chunks so that large inputs are
+/// hashed in constant memory.
+fn stream_into<R: Read>(mut reader: R, hasher: &mut dyn Digest) -> io::Result<()> {
+    let mut buf = vec![0; HASH_CHUNK_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.input(&buf[..n]);
+    }
 }
 
-fn execute_eth_signed_msg_cmd(input: &str) -> util::Res<String> {
-    let buf = read_hex_input(input)?;
+fn execute_hmac_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let key_hex = matches.value_of("key").unwrap();
+    let key = decode_hex(key_hex).map_err(|_| CmdError::InvalidArgument(format!("{} is not a hex-encoded key", key_hex)))?;
+    let macs: Vec<String> = read_hash_inputs(matches)?.iter().map(|data| {
+        let mac = match matches.value_of("alg").unwrap() {
+            "sha512" => compute_hmac(Sha512::new(), &key, data),
+            _ => compute_hmac(Sha256::new(), &key, data),
+        };
+        encode_hex(&mac)
+    }).collect();
+    Ok(macs.join("\n"))
+}
+
+fn compute_hmac<D: Digest>(digest: D, key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut hmac = Hmac::new(digest, key);
+    hmac.input(data);
+    hmac.result().code().to_vec()
+}
+
+/// Hashes a message with the `eth_sign` prefix, as `personal_sign` does.
+pub fn eth_signed_message_hash(msg: &[u8]) -> [u8; 32] {
     let mut hasher = Sha3::keccak256();
     hasher.input_str("\x19Ethereum Signed Message:\n");
-    hasher.input_str(buf.len().to_string().as_str());
-    hasher.input(buf.as_slice());
-    Ok(format!("0x{}", hasher.result_str()))
+    hasher.input_str(msg.len().to_string().as_str());
+    hasher.input(msg);
+    let mut out = [0; 32];
+    hasher.result(&mut out);
+    out
 }
 
-fn execute_decompose_sig_cmd(input: &str) -> util::Res<String> {
+/// The message length is part of the prefix, so the input is always buffered.
+fn execute_eth_signed_msg_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let hashes: Vec<String> = read_hash_inputs(matches)?.iter()
+        .map(|buf| encode_hex(&eth_signed_message_hash(buf)))
+        .collect();
+    Ok(hashes.join("\n"))
+}
+
+/// Hashes `0x19 || 0x00 || validator || data` as defined by EIP-191.
+pub fn validator_message_hash(validator: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha3::keccak256();
+    hasher.input(&[0x19, 0x00]);
+    hasher.input(validator);
+    hasher.input(data);
+    let mut out = [0; 32];
+    hasher.result(&mut out);
+    out
+}
+
+fn execute_validator_msg_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let validator_hex = matches.value_of("validator").unwrap();
+    let validator = decode_hex(validator_hex).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| CmdError::InvalidArgument(format!("{} is not a valid address", validator_hex)))?;
+    let hashes: Vec<String> = read_hash_inputs(matches)?.iter()
+        .map(|buf| encode_hex(&validator_message_hash(&validator, buf)))
+        .collect();
+    Ok(hashes.join("\n"))
+}
+
+/// Returns the secp256k1 curve order n and n / 2, the largest low-s value.
+fn secp256k1_order() -> (BigUint, BigUint) {
+    let order = BigUint::parse_bytes(SECP256K1_ORDER.as_bytes(), 16).unwrap();
+    let half_order = &order >> 1;
+    (order, half_order)
+}
+
+fn execute_decompose_sig_cmd(input: &str, json: bool) -> util::Res<String> {
     let buf = read_hex_input(input)?;
 
     if buf.len() != 65 {
         return Err(CryptoCmdError::InvalidSignatureLength.into());
     }
 
-    Ok(format!("R: {}\nS:{}\nV: {}", hex::encode(&buf[0..32]), hex::encode(&buf[32..64]), buf[64]))
+    let y_parity = match buf[64] {
+        0 | 1 => buf[64],
+        27 | 28 => buf[64] - 27,
+        v => return Err(CryptoCmdError::InvalidSignatureComponent("v", format!("{} is not 0, 1, 27, or 28", v)).into()),
+    };
+    // EIP-2098 stores the y parity in the top bit of s and requires low-s,
+    // so only signatures with s <= n / 2 have a compact form.
+    let (_, half_order) = secp256k1_order();
+    let compact = if BigUint::from_bytes_be(&buf[32..64]) <= half_order {
+        let mut compact = buf[..64].to_vec();
+        compact[32] |= y_parity << 7;
+        Some(encode_hex(&compact))
+    } else {
+        None
+    };
+
+    if json {
+        return Ok(serde_json::to_string_pretty(&json!({
+            "r": encode_hex(&buf[0..32]),
+            "s": encode_hex(&buf[32..64]),
+            "v": buf[64],
+            "yParity": y_parity,
+            "compact": compact,
+        }))?);
+    }
+    Ok(format!("R: {}\nS:{}\nV: {}\nCompact (EIP-2098): {}", hex::encode(&buf[0..32]), hex::encode(&buf[32..64]), buf[64],
+               compact.unwrap_or_else(|| String::from("unavailable; s is not in the lower half of the curve order"))))
 }
 
-fn execute_sign_cmd(input: &str, pk_hex: &str) -> util::Res<String> {
-    let input_buf = read_hex_input(input)?;
-    let pk_buf = decode_hex(pk_hex).map_err(|_| {
-        CryptoCmdError::InvalidPrivateKey
-    })?;
+fn execute_inspect_sig_cmd(input: &str) -> util::Res<String> {
+    let buf = read_hex_input(input)?;
+    if buf.len() != 65 {
+        return Err(CryptoCmdError::InvalidSignatureLength.into());
+    }
+
+    let (order, half_order) = secp256k1_order();
+    let r = BigUint::from_bytes_be(&buf[0..32]);
+    let s = BigUint::from_bytes_be(&buf[32..64]);
+    let mut problems = 0;
+    let mut check = |ok: bool, msg: String| {
+        if !ok {
+            problems += 1;
+        }
+        format!("{} {}", if ok { "[ok]  " } else { "[FAIL]" }, msg)
+    };
+
+    let mut lines = vec![
+        check(!r.is_zero(), String::from("r is nonzero")),
+        check(r < order, String::from("r is less than the curve order")),
+        check(!s.is_zero(), String::from("s is nonzero")),
+        check(s < order, String::from("s is less than the curve order")),
+    ];
+    // An out-of-range s already failed above and has no meaningful half.
+    if s < order {
+        lines.push(if s <= half_order {
+            check(true, String::from("s is in the lower half of the curve order"))
+        } else {
+            check(false, format!("s is in the upper half of the curve order and is malleable; the canonical form uses s = 0x{:064x} and flips v",
+                                 &order - &s))
+        });
+    }
+    lines.push(check(matches!(buf[64], 0 | 1 | 27 | 28), format!("v is 0, 1, 27, or 28 (got {})", buf[64])));
+
+    let summary = match problems {
+        0 => String::from("No problems found."),
+        1 => String::from("1 problem found."),
+        n => format!("{} problems found.", n),
+    };
+    Ok(format!("{}\n{}", lines.join("\n"), summary))
+}
+
+fn execute_expand_sig_cmd(input: &str) -> util::Res<String> {
+    let buf = read_hex_input(input)?;
+    if buf.len() != 64 {
+        return Err(CryptoCmdError::InvalidInputLength(64, buf.len()).into());
+    }
+
+    let mut out = buf.clone();
+    let y_parity = out[32] >> 7;
+    out[32] &= 0x7f;
+    out.push(y_parity + 27);
+    Ok(encode_hex(&out))
+}
+
+fn execute_compose_sig_cmd(r: &str, s: &str, v: &str) -> util::Res<String> {
+    let mut out = Vec::with_capacity(65);
+    for (name, component) in &[("r", r), ("s", s)] {
+        let buf = decode_hex(component)
+            .map_err(|_| CryptoCmdError::InvalidSignatureComponent(name, String::from("not hex-encoded")))?;
+        if buf.len() > 32 {
+            return Err(CryptoCmdError::InvalidSignatureComponent(name, format!("expected 32 bytes but got {}", buf.len())).into());
+        }
+        out.resize(out.len() + 32 - buf.len(), 0);
+        out.extend(buf);
+    }
+
+    match parse_v(v) {
+        Some(v @ 0..=1) | Some(v @ 27..=28) => out.push(v as u8),
+        _ => return Err(CryptoCmdError::InvalidSignatureComponent("v", format!("{} is not 0, 1, 27, or 28", v)).into()),
+    }
+    Ok(encode_hex(&out))
+}
+
+/// Parses a v value written in decimal or 0x-prefixed hex.
+fn parse_v(v: &str) -> Option<u64> {
+    match v.strip_prefix("0x") {
+        Some(h) => u64::from_str_radix(h, 16).ok(),
+        None => v.parse().ok(),
+    }
+}
 
+fn execute_convert_v_cmd(v: &str, chain_id: Option<&str>) -> util::Res<String> {
+    let v_num = parse_v(v).ok_or_else(|| CryptoCmdError::InvalidSignatureComponent("v", format!("{} is not a number", v)))?;
+    let chain_id = match chain_id {
+        Some(id) => Some(id.parse::<u64>()?),
+        None => None,
+    };
+
+    let (parity, chain_id) = match v_num {
+        0 | 1 => (v_num, chain_id),
+        27 | 28 => (v_num - 27, chain_id),
+        v_num if v_num >= 35 => {
+            let implied = (v_num - 35) / 2;
+            if let Some(id) = chain_id.filter(|id| *id != implied) {
+                return Err(CryptoCmdError::InvalidSignatureComponent("v", format!("{} implies chain ID {}, not {}", v_num, implied, id)).into());
+            }
+            ((v_num - 35) % 2, Some(implied))
+        }
+        _ => return Err(CryptoCmdError::InvalidSignatureComponent("v", format!("{} is not a valid v value", v_num)).into()),
+    };
+
+    let mut out = format!("Recovery ID: {}\nLegacy v: {}", parity, parity + 27);
+    if let Some(id) = chain_id {
+        let eip155 = id.checked_mul(2).and_then(|n| n.checked_add(35 + parity))
+            .ok_or_else(|| CmdError::InvalidArgument(format!("chain ID {} is too large", id)))?;
+        out.push_str(&format!("\nEIP-155 v: {} (chain ID {})", eip155, id));
+    }
+    Ok(out)
+}
+
+fn execute_sign_cmd(input: &str, pk_hex: &str, message: bool) -> util::Res<String> {
+    let input_buf = if message {
+        eth_signed_message_hash(&read_raw_input(input)?).to_vec()
+    } else {
+        read_hex_input(input)?
+    };
     if input_buf.len() != 32 {
         return Err(CryptoCmdError::InvalidInputLength(32, input_buf.len()).into());
     }
 
     let secp = Secp256k1::new();
-    let pk = SecretKey::from_slice(pk_buf.as_slice()).expect("32 bytes, within curve order");
-    let msg = Message::from_slice(input_buf.as_slice()).expect("32 bytes");
+    let pk = parse_private_key(pk_hex)?;
+    let msg = Message::from_slice(input_buf.as_slice())?;
     let sig = secp.sign_recoverable(&msg, &pk);
     let ser = sig.serialize_compact();
     let id = ser.0.to_i32() as u8;
     let mut out = ser.1.to_vec();
-    out.write(&[id + 27])?;
+    out.write_all(&[id + 27])?;
     Ok(encode_hex(&out))
-}
\ No newline at end of file
+}
+
+fn execute_verify_cmd(input: &str, address: &str, sig_hex: &str) -> util::Res<String> {
+    let hash = read_hex_input(input)?;
+    let expected = decode_hex(address).ok().filter(|a| a.len() == 20)
+        .ok_or_else(|| CmdError::InvalidArgument(format!("{} is not a valid address", address)))?;
+    let sig = decode_hex(sig_hex).map_err(|_| CryptoCmdError::InvalidSignature)?;
+
+    let recovered = public_key_to_address(&recover_public_key(&hash, &sig)?);
+    if recovered[..] != expected[..] {
+        return Err(CryptoCmdError::SignerMismatch(encode_hex(&expected), encode_hex(&recovered)).into());
+    }
+    Ok(format!("Valid signature from {}", encode_hex(&recovered)))
+}
+
+/// Computes the x coordinate of the point `pub_k * priv_k`.
+pub fn ecdh_shared_secret(priv_k: &SecretKey, pub_k: &PublicKey) -> Result<[u8; 32], CryptoCmdError> {
+    let mut point = *pub_k;
+    point.mul_assign(&Secp256k1::new(), &priv_k[..]).map_err(|_| CryptoCmdError::InvalidPrivateKey)?;
+    let mut out = [0; 32];
+    out.copy_from_slice(&point.serialize()[1..]);
+    Ok(out)
+}
+
+fn execute_ecdh_cmd(pk_hex: &str, pub_hex: &str) -> util::Res<String> {
+    let secret = ecdh_shared_secret(&parse_private_key(pk_hex)?, &parse_sec1_public_key(pub_hex)?)?;
+    let mut sha = Sha256::new();
+    sha.input(&secret);
+    Ok(format!("Shared secret: {}\nKeccak256: {}\nSHA256: 0x{}",
+               encode_hex(&secret), encode_hex(&keccak256(&secret)), sha.result_str()))
+}
+
+/// The NIST SP 800-56 concatenation KDF over SHA-256, as used by devp2p ECIES.
+fn concat_kdf(secret: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    let mut counter: u32 = 1;
+    while out.len() < len {
+        let mut hasher = Sha256::new();
+        hasher.input(&counter.to_be_bytes());
+        hasher.input(secret);
+        let mut block = [0; 32];
+        hasher.result(&mut block);
+        out.extend_from_slice(&block);
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+/// Derives the AES-128 and HMAC-SHA256 keys from an ECDH shared secret.
+fn ecies_keys(secret: &[u8]) -> (Vec<u8>, [u8; 32]) {
+    let keys = concat_kdf(secret, 32);
+    let mut mac_key = [0; 32];
+    let mut hasher = Sha256::new();
+    hasher.input(&keys[16..]);
+    hasher.result(&mut mac_key);
+    (keys[..16].to_vec(), mac_key)
+}
+
+fn ecies_mac(mac_key: &[u8], iv: &[u8], ciphertext: &[u8], shared_mac_data: &[u8]) -> MacResult {
+    let mut hmac = Hmac::new(Sha256::new(), mac_key);
+    hmac.input(iv);
+    hmac.input(ciphertext);
+    hmac.input(shared_mac_data);
+    hmac.result()
+}
+
+/// Encrypts `msg` to `pub_k`, producing `R || iv || ciphertext || mac` where
+/// `R` is the uncompressed ephemeral public key.
+pub fn ecies_encrypt(pub_k: &PublicKey, msg: &[u8], shared_mac_data: &[u8]) -> util::Res<Vec<u8>> {
+    let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+    let (ephemeral_priv, ephemeral_pub) = Secp256k1::new().generate_keypair(&mut rng);
+    let mut iv = [0; ECIES_IV_SIZE];
+    rng.fill_bytes(&mut iv);
+
+    let (enc_key, mac_key) = ecies_keys(&ecdh_shared_secret(&ephemeral_priv, pub_k)?);
+    let mut ciphertext = vec![0; msg.len()];
+    aes::ctr(KeySize::KeySize128, &enc_key, &iv).process(msg, &mut ciphertext);
+
+    let mut out = ephemeral_pub.serialize_uncompressed().to_vec();
+    out.extend_from_slice(&iv);
+    out.extend_from_slice(&ciphertext);
+    out.extend_from_slice(ecies_mac(&mac_key, &iv, &ciphertext, shared_mac_data).code());
+    Ok(out)
+}
+
+/// Decrypts data produced by `ecies_encrypt`, checking the MAC first.
+pub fn ecies_decrypt(priv_k: &SecretKey, data: &[u8], shared_mac_data: &[u8]) -> Result<Vec<u8>, CryptoCmdError> {
+    if data.len() < 65 + ECIES_IV_SIZE + 32 {
+        return Err(CryptoCmdError::InvalidCiphertext);
+    }
+
+    let (ephemeral_pub, rest) = data.split_at(65);
+    let (iv, rest) = rest.split_at(ECIES_IV_SIZE);
+    let (ciphertext, mac) = rest.split_at(rest.len() - 32);
+    let ephemeral_pub = PublicKey::from_slice(ephemeral_pub).map_err(|_| CryptoCmdError::InvalidCiphertext)?;
+
+    let (enc_key, mac_key) = ecies_keys(&ecdh_shared_secret(priv_k, &ephemeral_pub)?);
+    if ecies_mac(&mac_key, iv, ciphertext, shared_mac_data) != MacResult::new(mac) {
+        return Err(CryptoCmdError::InvalidCiphertext);
+    }
+
+    let mut out = vec![0; ciphertext.len()];
+    aes::ctr(KeySize::KeySize128, &enc_key, iv).process(ciphertext, &mut out);
+    Ok(out)
+}
+
+fn parse_shared_mac_data(data: Option<&str>) -> util::Res<Vec<u8>> {
+    match data {
+        Some(d) => decode_hex(d).map_err(|_| CmdError::InvalidArgument(format!("{} is not hex-encoded", d)).into()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn execute_ecies_encrypt_cmd(input: &str, pub_hex: &str, shared_mac_data: Option<&str>) -> util::Res<String> {
+    let msg = read_hex_input(input)?;
+    let pub_k = parse_sec1_public_key(pub_hex)?;
+    Ok(encode_hex(&ecies_encrypt(&pub_k, &msg, &parse_shared_mac_data(shared_mac_data)?)?))
+}
+
+fn execute_ecies_decrypt_cmd(input: &str, pk_hex: &str, shared_mac_data: Option<&str>) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let priv_k = parse_private_key(pk_hex)?;
+    Ok(encode_hex(&ecies_decrypt(&priv_k, &data, &parse_shared_mac_data(shared_mac_data)?)?))
+}
+
+/// Derives a key with scrypt, validating the parameters up front since the
+/// underlying implementation panics on invalid ones.
+pub fn scrypt_key(password: &[u8], salt: &[u8], n: u64, r: u32, p: u32, dklen: usize) -> Result<Vec<u8>, CryptoCmdError> {
+    if n < 2 || !n.is_power_of_two() {
+        return Err(CryptoCmdError::InvalidKdfParams(format!("n must be a power of two greater than 1, got {}", n)));
+    }
+    let log_n = n.trailing_zeros();
+    if r == 0 || p == 0 {
+        return Err(CryptoCmdError::InvalidKdfParams(String::from("r and p must be nonzero")));
+    }
+    if log_n as u64 >= r as u64 * 16 || (r as u64) * (p as u64) >= 1 << 30 {
+        return Err(CryptoCmdError::InvalidKdfParams(format!("n={}, r={}, p={} are out of range", n, r, p)));
+    }
+    if (r as u64 * 128).checked_mul(n).filter(|m| *m <= MAX_SCRYPT_MEMORY).is_none() {
+        return Err(CryptoCmdError::InvalidKdfParams(format!("n={} and r={} require more than {} bytes of memory", n, r, MAX_SCRYPT_MEMORY)));
+    }
+    if dklen == 0 {
+        return Err(CryptoCmdError::InvalidKdfParams(String::from("dklen must be nonzero")));
+    }
+
+    let mut out = vec![0; dklen];
+    scrypt::scrypt(password, salt, &ScryptParams::new(log_n as u8, r, p), &mut out);
+    Ok(out)
+}
+
+/// Derives a key with PBKDF2 using HMAC over the given digest.
+pub fn pbkdf2_key<D: Digest>(digest: D, password: &[u8], salt: &[u8], iterations: u32, dklen: usize) -> Result<Vec<u8>, CryptoCmdError> {
+    if iterations == 0 || dklen == 0 {
+        return Err(CryptoCmdError::InvalidKdfParams(String::from("iterations and dklen must be nonzero")));
+    }
+
+    let mut out = vec![0; dklen];
+    pbkdf2::pbkdf2(&mut Hmac::new(digest, password), salt, iterations, &mut out);
+    Ok(out)
+}
+
+/// Encrypts a private key as a Web3 Secret Storage (V3) keystore using
+/// scrypt and AES-128-CTR.
+pub fn encrypt_keystore(priv_k: &SecretKey, password: &[u8]) -> util::Res<serde_json::Value> {
+    let mut rng = OsRng::new().map_err(|_| CmdError::Unavailable(String::from("the OS random number generator")))?;
+    let mut salt = [0; 32];
+    let mut iv = [0; 16];
+    let mut id = [0; 16];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+    rng.fill_bytes(&mut id);
+    // Mark the random bytes as a version 4, RFC 4122 variant UUID.
+    id[6] = (id[6] & 0x0f) | 0x40;
+    id[8] = (id[8] & 0x3f) | 0x80;
+
+    let derived = scrypt_key(password, &salt, KEYSTORE_SCRYPT_N, KEYSTORE_SCRYPT_R, KEYSTORE_SCRYPT_P, 32)?;
+    let mut ciphertext = [0; 32];
+    aes::ctr(KeySize::KeySize128, &derived[..16], &iv).process(&priv_k[..], &mut ciphertext);
+    let mut mac_input = derived[16..].to_vec();
+    mac_input.extend_from_slice(&ciphertext);
+
+    let pub_k = PublicKey::from_secret_key(&Secp256k1::new(), priv_k);
+    Ok(json!({
+        "address": hex::encode(public_key_to_address(&pub_k)),
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "ciphertext": hex::encode(ciphertext),
+            "cipherparams": { "iv": hex::encode(iv) },
+            "kdf": "scrypt",
+            "kdfparams": {
+                "dklen": 32,
+                "n": KEYSTORE_SCRYPT_N,
+                "p": KEYSTORE_SCRYPT_P,
+                "r": KEYSTORE_SCRYPT_R,
+                "salt": hex::encode(salt),
+            },
+            "mac": hex::encode(keccak256(&mac_input)),
+        },
+        "id": format!("{}-{}-{}-{}-{}", hex::encode(&id[..4]), hex::encode(&id[4..6]), hex::encode(&id[6..8]), hex::encode(&id[8..10]), hex::encode(&id[10..])),
+        "version": 3,
+    }))
+}
+
+/// Reads the password, salt, and key length shared by the KDF commands.
+fn read_kdf_args(matches: &ArgMatches) -> util::Res<(Vec<u8>, Vec<u8>, usize)> {
+    let password = read_raw_input(matches.value_of("input").unwrap())?;
+    let salt_hex = matches.value_of("salt").unwrap();
+    let salt = decode_hex(salt_hex).map_err(|_| CmdError::InvalidArgument(format!("{} is not a hex-encoded salt", salt_hex)))?;
+    let dklen = matches.value_of("dklen").unwrap().parse()?;
+    Ok((password, salt, dklen))
+}
+
+fn execute_scrypt_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let (password, salt, dklen) = read_kdf_args(matches)?;
+    let n = matches.value_of("n").unwrap().parse()?;
+    let r = matches.value_of("r").unwrap().parse()?;
+    let p = matches.value_of("p").unwrap().parse()?;
+    Ok(encode_hex(&scrypt_key(&password, &salt, n, r, p, dklen)?))
+}
+
+fn execute_pbkdf2_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let (password, salt, dklen) = read_kdf_args(matches)?;
+    let iterations = matches.value_of("iterations").unwrap().parse()?;
+    let key = match matches.value_of("prf").unwrap() {
+        "sha512" => pbkdf2_key(Sha512::new(), &password, &salt, iterations, dklen)?,
+        _ => pbkdf2_key(Sha256::new(), &password, &salt, iterations, dklen)?,
+    };
+    Ok(encode_hex(&key))
+}
+
+/// A keccak256 Merkle tree whose pairs are sorted before hashing, so proofs
+/// need no position bits. An unpaired node is promoted to the next layer
+/// unchanged.
+struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    fn new(leaves: Vec<[u8; 32]>) -> Result<MerkleTree, CryptoCmdError> {
+        if leaves.is_empty() {
+            return Err(CryptoCmdError::EmptyTree);
+        }
+
+        let mut layers = vec![leaves];
+        while layers.last().unwrap().len() > 1 {
+            let next = layers.last().unwrap().chunks(2).map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            }).collect();
+            layers.push(next);
+        }
+        Ok(MerkleTree { layers })
+    }
+
+    fn root(&self) -> [u8; 32] {
+        self.layers.last().unwrap()[0]
+    }
+
+    fn proof(&self, mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut buf = [0; 64];
+    buf[..32].copy_from_slice(lo);
+    buf[32..].copy_from_slice(hi);
+    keccak256(&buf)
+}
+
+fn parse_merkle_leaf(leaf: &str, hash: bool) -> Result<[u8; 32], CryptoCmdError> {
+    let buf = decode_hex(leaf).map_err(|_| CryptoCmdError::InvalidLeaf(String::from(leaf)))?;
+    if hash {
+        return Ok(keccak256(&buf));
+    }
+    if buf.len() != 32 {
+        return Err(CryptoCmdError::InvalidLeaf(String::from(leaf)));
+    }
+    let mut out = [0; 32];
+    out.copy_from_slice(&buf);
+    Ok(out)
+}
+
+fn read_merkle_leaves(matches: &ArgMatches) -> util::Res<Vec<[u8; 32]>> {
+    let input = matches.value_of("input").unwrap();
+    let data = if input == "-" { read_raw_input(input)? } else { fs::read(input)? };
+    let text = String::from_utf8(data).map_err(|_| CmdError::InvalidInput(String::from("leaves must be UTF-8 text")))?;
+    let hash = matches.is_present("hash-leaves");
+    Ok(text.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .map(|l| parse_merkle_leaf(l, hash))
+        .collect::<Result<Vec<[u8; 32]>, CryptoCmdError>>()?)
+}
+
+fn execute_merkle_proof_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let leaves = read_merkle_leaves(matches)?;
+    let tree = MerkleTree::new(leaves.clone())?;
+    let only = match matches.value_of("leaf") {
+        Some(l) => Some(parse_merkle_leaf(l, matches.is_present("hash-leaves"))?),
+        None => None,
+    };
+
+    let mut proofs = Vec::new();
+    for (i, leaf) in leaves.iter().enumerate() {
+        if only.is_some_and(|o| o != *leaf) {
+            continue;
+        }
+        let proof: Vec<String> = tree.proof(i).iter().map(|p| encode_hex(p)).collect();
+        proofs.push(json!({ "leaf": encode_hex(leaf), "index": i, "proof": proof }));
+    }
+    if let (Some(leaf), true) = (matches.value_of("leaf"), proofs.is_empty()) {
+        return Err(CmdError::InvalidArgument(format!("{} is not a leaf of the tree", leaf)).into());
+    }
+
+    Ok(serde_json::to_string_pretty(&json!({ "root": encode_hex(&tree.root()), "proofs": proofs }))?)
+}
+
+fn make_blob_cmd<'a, 'b>(name: &'a str) -> App<'a, 'b> {
+    SubCommand::with_name(name)
+        .arg(Arg::with_name("input")
+            .short("-i")
+            .takes_value(true)
+            .required_unless("file")
+            .conflicts_with("file")
+            .help("The hex-encoded 131072-byte blob. If - is provided, will read from stdin."))
+        .arg(Arg::with_name("file")
+            .long("file")
+            .takes_value(true)
+            .help("Reads the blob as raw bytes from a file."))
+}
+
+/// Reads a blob from `--file` or `-i`, requiring exactly `BYTES_PER_BLOB` bytes.
+fn read_blob(matches: &ArgMatches) -> util::Res<Blob> {
+    let data = match matches.value_of("file") {
+        Some(path) => fs::read(path)?,
+        None => read_hex_input(matches.value_of("input").unwrap())?,
+    };
+    if data.len() != BYTES_PER_BLOB {
+        return Err(CryptoCmdError::InvalidInputLength(BYTES_PER_BLOB, data.len()).into());
+    }
+    Blob::from_bytes(&data).map_err(|_| CryptoCmdError::InvalidBlob.into())
+}
+
+fn execute_kzg_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("commit", Some(sub)) => {
+            let commitment = ethereum_kzg_settings(0).blob_to_kzg_commitment(&read_blob(sub)?)
+                .map_err(|_| CryptoCmdError::InvalidBlob)?;
+            Ok(encode_hex(&commitment.to_bytes().into_inner()))
+        }
+        ("proof", Some(sub)) => {
+            let blob = read_blob(sub)?;
+            let settings = ethereum_kzg_settings(0);
+            let commitment = settings.blob_to_kzg_commitment(&blob)
+                .map_err(|_| CryptoCmdError::InvalidBlob)?;
+            let proof = settings.compute_blob_kzg_proof(&blob, &commitment.to_bytes())
+                .map_err(|_| CryptoCmdError::InvalidBlob)?;
+            Ok(encode_hex(&proof.to_bytes().into_inner()))
+        }
+        ("versioned-hash", Some(sub)) => {
+            let commitment = read_hex_input(sub.value_of("input").unwrap())?;
+            if commitment.len() != KZG_COMMITMENT_SIZE {
+                return Err(CryptoCmdError::InvalidInputLength(KZG_COMMITMENT_SIZE, commitment.len()).into());
+            }
+            Ok(encode_hex(&kzg_versioned_hash(&commitment)))
+        }
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+/// Computes `VERSIONED_HASH_VERSION_KZG || sha256(commitment)[1..]`.
+pub fn kzg_versioned_hash(commitment: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.input(commitment);
+    let mut out = [0; 32];
+    hasher.result(&mut out);
+    out[0] = VERSIONED_HASH_VERSION_KZG;
+    out
+}
+
+/// Adds public keys as points. The result is compressed if the first input was.
+fn execute_point_add_cmd(pub_hexes: Vec<&str>) -> util::Res<String> {
+    let compressed = decode_hex(pub_hexes[0]).map(|b| b.len() == 33).unwrap_or(false);
+    let mut sum = parse_sec1_public_key(pub_hexes[0])?;
+    for pub_hex in &pub_hexes[1..] {
+        sum = sum.combine(&parse_sec1_public_key(pub_hex)?)
+            .map_err(|_| CmdError::InvalidInput(String::from("the points sum to the point at infinity")))?;
+    }
+
+    if compressed {
+        Ok(encode_hex(&sum.serialize()))
+    } else {
+        Ok(encode_hex(&sum.serialize_uncompressed()))
+    }
+}
+
+/// Encodes 16 to 32 bytes of entropy as a BIP-39 English mnemonic.
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(entropy);
+    let mut checksum = [0; 32];
+    hasher.result(&mut checksum);
+
+    let mut bits: Vec<bool> = entropy.iter().chain(checksum.iter())
+        .flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+        .collect();
+    bits.truncate(entropy.len() * 8 + entropy.len() / 4);
+
+    let words: Vec<&str> = BIP39_ENGLISH.lines().collect();
+    bits.chunks(11)
+        .map(|chunk| words[chunk.iter().fold(0, |acc, b| acc << 1 | *b as usize)])
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Checks that a mnemonic uses English BIP-39 words and has a valid checksum.
+pub fn validate_mnemonic(mnemonic: &str) -> Result<(), CryptoCmdError> {
+    let words: Vec<&str> = BIP39_ENGLISH.lines().collect();
+    let phrase: Vec<&str> = mnemonic.split_whitespace().collect();
+    if phrase.len() < 12 || phrase.len() > 24 || !phrase.len().is_multiple_of(3) {
+        return Err(CryptoCmdError::InvalidMnemonic(format!("expected 12, 15, 18, 21, or 24 words but got {}", phrase.len())));
+    }
+
+    let mut bits = Vec::with_capacity(phrase.len() * 11);
+    for word in &phrase {
+        let index = words.binary_search(word)
+            .map_err(|_| CryptoCmdError::InvalidMnemonic(format!("{} is not in the BIP-39 English wordlist", word)))?;
+        bits.extend((0..11).rev().map(|i| index >> i & 1 == 1));
+    }
+    let entropy: Vec<u8> = bits[..phrase.len() / 3 * 32].chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, b| acc << 1 | *b as u8))
+        .collect();
+    if entropy_to_mnemonic(&entropy) != phrase.join(" ") {
+        return Err(CryptoCmdError::InvalidMnemonic(String::from("the checksum does not match")));
+    }
+    Ok(())
+}
+
+/// Derives the BIP-39 seed for a mnemonic without validating its checksum.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Vec<u8> {
+    let mnemonic = mnemonic.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let salt = format!("mnemonic{}", passphrase);
+    pbkdf2_key(Sha512::new(), mnemonic.as_bytes(), salt.as_bytes(), 2048, 64).unwrap()
+}
+
+/// Parses a BIP-32 path such as `m/44'/60'/0'/0/0` into child indices.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>, CryptoCmdError> {
+    let err = || CryptoCmdError::InvalidDerivationPath(String::from(path));
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(err());
+    }
+    parts.map(|p| {
+        let (num, hardened) = match p.strip_suffix('\'').or_else(|| p.strip_suffix('h')) {
+            Some(n) => (n, true),
+            None => (p, false),
+        };
+        let index: u32 = num.parse().map_err(|_| err())?;
+        if index >= BIP32_HARDENED {
+            return Err(err());
+        }
+        Ok(if hardened { index + BIP32_HARDENED } else { index })
+    }).collect()
+}
+
+/// Derives a BIP-32 private key from a seed along a path of child indices.
+pub fn derive_bip32_key(seed: &[u8], path: &[u32]) -> Result<SecretKey, CryptoCmdError> {
+    let master = compute_hmac(Sha512::new(), b"Bitcoin seed", seed);
+    let mut key = SecretKey::from_slice(&master[..32]).map_err(|_| CryptoCmdError::PrivateKeyOutOfRange)?;
+    let mut chain_code = master[32..].to_vec();
+
+    let secp = Secp256k1::new();
+    for index in path {
+        let mut data = Vec::with_capacity(37);
+        if *index >= BIP32_HARDENED {
+            data.push(0);
+            data.extend_from_slice(&key[..]);
+        } else {
+            data.extend_from_slice(&PublicKey::from_secret_key(&secp, &key).serialize());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let out = compute_hmac(Sha512::new(), &chain_code, &data);
+        key.add_assign(&out[..32]).map_err(|_| CryptoCmdError::PrivateKeyOutOfRange)?;
+        chain_code = out[32..].to_vec();
+    }
+    Ok(key)
+}
+
+enum Tweak {
+    Add,
+    Mul,
+}
+
+fn execute_tweak_cmd(op: Tweak, private_key: Option<&str>, public_key: Option<&str>, tweak_hex: &str) -> util::Res<String> {
+    let tweak = decode_hex(tweak_hex).map_err(|_| CryptoCmdError::InvalidTweak)?;
+    if tweak.len() != 32 {
+        return Err(CryptoCmdError::InvalidTweak.into());
+    }
+
+    if let Some(pk_hex) = private_key {
+        let mut pk = parse_private_key(pk_hex)?;
+        match op {
+            Tweak::Add => pk.add_assign(&tweak),
+            Tweak::Mul => pk.mul_assign(&tweak),
+        }.map_err(|_| CryptoCmdError::InvalidTweak)?;
+        return Ok(format!("0x{}", pk));
+    }
+
+    let pub_hex = public_key.ok_or_else(|| CmdError::InvalidArgument(String::from("a private or public key is required")))?;
+    let compressed = decode_hex(pub_hex).map(|b| b.len() == 33).unwrap_or(false);
+    let mut pub_k = parse_public_key(pub_hex)?;
+    let secp = Secp256k1::new();
+    match op {
+        Tweak::Add => pub_k.add_exp_assign(&secp, &tweak),
+        Tweak::Mul => pub_k.mul_assign(&secp, &tweak),
+    }.map_err(|_| CryptoCmdError::InvalidTweak)?;
+
+    if compressed {
+        Ok(encode_hex(&pub_k.serialize()))
+    } else {
+        Ok(encode_hex(&pub_k.serialize_uncompressed()))
+    }
+}
@@ -1,6 +1,36 @@
 use clap::{App, SubCommand, ArgMatches, Arg};
-use crate::util::{make_input_arg, read_raw_input, CmdError};
+use crate::util::{make_input_arg, read_hex_input, read_raw_input, encode_hex, CmdError};
 use crate::util;
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{general_purpose, DecodePaddingMode, GeneralPurpose};
+use std::io::{self, Write};
+use std::{error, fmt};
+use num_bigint::BigUint;
+
+const BECH32_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+/// The constants XORed into the checksum by bech32 (BIP-173) and bech32m (BIP-350).
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+/// The widest `--pad` to-hex-num accepts, in bytes.
+const MAX_HEX_NUM_PAD: usize = 1 << 20;
+
+#[derive(Debug)]
+pub enum EncodeError {
+    InvalidBech32(String),
+    InvalidHrp(String),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            EncodeError::InvalidBech32(reason) => write!(f, "invalid bech32 string: {}", reason),
+            EncodeError::InvalidHrp(hrp) => write!(f, "{} is not a valid human-readable part; it must be 1 to 83 printable ASCII characters", hrp),
+        }
+    }
+}
+
+impl error::Error for EncodeError {}
 
 pub fn make_encode_cmd<'a, 'b>() -> App<'a, 'b> {
     let encode_cmd = SubCommand::with_name("hex")
@@ -11,15 +41,110 @@ pub fn make_encode_cmd<'a, 'b>() -> App<'a, 'b> {
             .possible_values(&["utf-8"])
             .default_value("utf-8"))
         .about("encodes the input as hex");
+    let utf8_cmd = SubCommand::with_name("utf-8")
+        .arg(make_input_arg("the hex-encoded bytes to decode. if - is provided, will read from stdin"))
+        .arg(Arg::with_name("lossy")
+            .long("lossy")
+            .help("Replaces invalid UTF-8 sequences with U+FFFD instead of failing."))
+        .about("decodes hex into UTF-8 text, escaping non-printable characters");
+
+    let base64_cmd = SubCommand::with_name("base64")
+        .arg(make_input_arg("the input to encode or decode. if - is provided, will read from stdin"))
+        .arg(Arg::with_name("input-encoding")
+            .short("-e")
+            .help("the input's encoding when encoding")
+            .possible_values(&["utf-8", "hex"])
+            .default_value("utf-8"))
+        .arg(Arg::with_name("decode")
+            .short("-d")
+            .long("decode")
+            .help("Decodes base64 into hex instead of encoding. Padding is optional."))
+        .arg(Arg::with_name("raw")
+            .long("raw")
+            .requires("decode")
+            .help("Writes the decoded bytes to stdout as-is instead of hex-encoding them."))
+        .arg(Arg::with_name("url-safe")
+            .long("url-safe")
+            .help("Uses the URL-safe alphabet, with - and _ in place of + and /."))
+        .arg(Arg::with_name("no-pad")
+            .long("no-pad")
+            .conflicts_with("decode")
+            .help("Omits trailing = padding when encoding."))
+        .about("encodes the input as base64, or decodes base64");
+
+    let bech32_cmd = SubCommand::with_name("bech32")
+        .arg(make_input_arg("the hex-encoded data to encode, or the bech32 string to decode. if - is provided, will read from stdin"))
+        .arg(Arg::with_name("hrp")
+            .long("hrp")
+            .takes_value(true)
+            .required_unless("decode")
+            .help("The human-readable part, e.g. cosmos."))
+        .arg(Arg::with_name("variant")
+            .long("variant")
+            .takes_value(true)
+            .possible_values(&["bech32", "bech32m"])
+            .help("The checksum variant. Defaults to bech32 when encoding; when decoding, either is accepted unless one is given."))
+        .arg(Arg::with_name("decode")
+            .short("-d")
+            .long("decode")
+            .help("Decodes a bech32 string into its human-readable part, variant, and hex data."))
+        .about("encodes data as bech32 or bech32m, or decodes it");
+
+    let binary_cmd = SubCommand::with_name("binary")
+        .arg(make_input_arg("the hex-encoded data to print, or the bits to parse. if - is provided, will read from stdin"))
+        .arg(Arg::with_name("decode")
+            .short("-d")
+            .long("decode")
+            .help("Parses a 0b-prefixed or bare bit string into hex. Spaces and underscores are ignored, and the bits are left-padded to a whole byte."))
+        .about("prints the bits of hex-encoded data grouped per byte, or parses bits into hex");
+
+    let to_hex_num_cmd = SubCommand::with_name("to-hex-num")
+        .arg(Arg::with_name("number")
+            .help("the non-negative decimal integer to convert")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("pad")
+            .long("pad")
+            .takes_value(true)
+            .help("Left-pads the output with zeros to this many bytes, e.g. 32 for a uint256 word."))
+        .about("converts a decimal integer of any size to hex, as a JSON-RPC quantity unless --pad is given");
+    let from_hex_num_cmd = SubCommand::with_name("from-hex-num")
+        .arg(Arg::with_name("number")
+            .help("the 0x-prefixed hex integer to convert")
+            .index(1)
+            .required(true))
+        .about("converts a hex integer of any size to decimal");
+
+    let swap_endian_cmd = SubCommand::with_name("swap-endian")
+        .arg(make_input_arg("the hex-encoded bytes to swap. if - is provided, will read from stdin"))
+        .arg(Arg::with_name("chunk")
+            .long("chunk")
+            .takes_value(true)
+            .help("Reverses each chunk of this many bytes in place instead of the whole input, e.g. 8 for a list of uint64s."))
+        .about("reverses the byte order of hex-encoded data, e.g. to convert little-endian SSZ values to big-endian EVM values");
 
     SubCommand::with_name("encode")
         .subcommand(encode_cmd)
+        .subcommand(utf8_cmd)
+        .subcommand(base64_cmd)
+        .subcommand(bech32_cmd)
+        .subcommand(binary_cmd)
+        .subcommand(to_hex_num_cmd)
+        .subcommand(from_hex_num_cmd)
+        .subcommand(swap_endian_cmd)
         .about("Convert data from one format to another.")
 }
 
 pub fn execute_encode_cmd(matches: &ArgMatches) -> util::Res<String> {
     match matches.subcommand() {
         ("hex", Some(sub)) => execute_encode_hex_cmd(sub.value_of("input").unwrap(), sub.value_of("input-encoding").unwrap()),
+        ("base64", Some(sub)) => execute_base64_cmd(sub),
+        ("bech32", Some(sub)) => execute_bech32_cmd(sub),
+        ("to-hex-num", Some(sub)) => execute_to_hex_num_cmd(sub.value_of("number").unwrap(), sub.value_of("pad")),
+        ("from-hex-num", Some(sub)) => execute_from_hex_num_cmd(sub.value_of("number").unwrap()),
+        ("swap-endian", Some(sub)) => execute_swap_endian_cmd(sub.value_of("input").unwrap(), sub.value_of("chunk")),
+        ("binary", Some(sub)) => execute_binary_cmd(sub.value_of("input").unwrap(), sub.is_present("decode")),
+        ("utf-8", Some(sub)) => execute_utf8_cmd(sub.value_of("input").unwrap(), sub.is_present("lossy")),
         (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
     }
 }
@@ -28,13 +153,222 @@ fn execute_encode_hex_cmd(input: &str, encoding: &str) -> util::Res<String> {
     let buf = read_raw_input(input)?;
     let res = match encoding {
         "utf-8" => {
-            let encoded = hex::encode(buf);
-            String::from(encoded)
-        }
-        _ => {
-            panic!("invalid input encoding; should have been caught by CLI crate");
+            hex::encode(buf)
         }
+        e => return Err(CmdError::InvalidArgument(format!("unsupported input encoding {}", e)).into())
     };
 
     Ok(format!("0x{}", res))
-}
\ No newline at end of file
+}
+
+fn execute_utf8_cmd(input: &str, lossy: bool) -> util::Res<String> {
+    let buf = read_hex_input(input)?;
+    let text = if lossy {
+        String::from_utf8_lossy(&buf).into_owned()
+    } else {
+        String::from_utf8(buf).map_err(|e| CmdError::InvalidInput(format!("{}; pass --lossy to replace invalid sequences", e)))?
+    };
+
+    Ok(text.chars().map(escape_char).collect())
+}
+
+/// Escapes backslashes and control characters so the output is printable
+/// and unambiguous.
+fn escape_char(c: char) -> String {
+    match c {
+        '\\' => String::from("\\\\"),
+        '\n' => String::from("\\n"),
+        '\r' => String::from("\\r"),
+        '\t' => String::from("\\t"),
+        c if c.is_ascii_control() => format!("\\x{:02x}", c as u32),
+        c if c.is_control() => c.escape_unicode().to_string(),
+        c => c.to_string(),
+    }
+}
+
+fn execute_base64_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let input = matches.value_of("input").unwrap();
+    let alphabet = if matches.is_present("url-safe") { &alphabet::URL_SAFE } else { &alphabet::STANDARD };
+
+    if matches.is_present("decode") {
+        let data = read_raw_input(input)?;
+        let data: Vec<u8> = data.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        let config = general_purpose::PAD.with_decode_padding_mode(DecodePaddingMode::Indifferent);
+        let decoded = GeneralPurpose::new(alphabet, config).decode(data)
+            .map_err(|e| CmdError::InvalidInput(format!("invalid base64: {}", e)))?;
+        if matches.is_present("raw") {
+            io::stdout().write_all(&decoded)?;
+            return Ok(String::new());
+        }
+        return Ok(encode_hex(&decoded));
+    }
+
+    let data = match matches.value_of("input-encoding").unwrap() {
+        "hex" => read_hex_input(input)?,
+        _ => read_raw_input(input)?,
+    };
+    let config = if matches.is_present("no-pad") { general_purpose::NO_PAD } else { general_purpose::PAD };
+    Ok(GeneralPurpose::new(alphabet, config).encode(data))
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATORS: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+    values.iter().fold(1, |chk, v| {
+        let top = chk >> 25;
+        let chk = (chk & 0x1ff_ffff) << 5 ^ *v as u32;
+        GENERATORS.iter().enumerate().fold(chk, |chk, (i, g)| if (top >> i) & 1 == 1 { chk ^ g } else { chk })
+    })
+}
+
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    out.push(0);
+    out.extend(hrp.bytes().map(|b| b & 31));
+    out
+}
+
+/// Regroups bits from `from`-bit to `to`-bit words, as bech32 does to move
+/// between bytes and 5-bit characters.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let max = (1 << to) - 1;
+    let mut out = Vec::new();
+    for value in data {
+        acc = (acc << from) | *value as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad && bits > 0 {
+        out.push(((acc << (to - bits)) & max) as u8);
+    } else if !pad && (bits >= from || (acc << (to - bits)) & max != 0) {
+        return None;
+    }
+    Some(out)
+}
+
+pub fn bech32_encode(hrp: &str, data: &[u8], bech32m: bool) -> Result<String, EncodeError> {
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(EncodeError::InvalidHrp(String::from(hrp)));
+    }
+    let hrp = hrp.to_lowercase();
+    let mut values = convert_bits(data, 8, 5, true).unwrap_or_default();
+    let mut checksum_input = bech32_hrp_expand(&hrp);
+    checksum_input.extend_from_slice(&values);
+    checksum_input.extend_from_slice(&[0; 6]);
+    let checksum = bech32_polymod(&checksum_input) ^ if bech32m { BECH32M_CONST } else { BECH32_CONST };
+    values.extend((0..6).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8));
+
+    let chars: String = values.iter().map(|v| BECH32_CHARSET[*v as usize] as char).collect();
+    Ok(format!("{}1{}", hrp, chars))
+}
+
+/// Decodes a bech32 or bech32m string into its human-readable part, data,
+/// and whether it used the bech32m checksum.
+pub fn bech32_decode(input: &str) -> Result<(String, Vec<u8>, bool), EncodeError> {
+    let invalid = |reason: &str| EncodeError::InvalidBech32(String::from(reason));
+    if input.chars().any(|c| c.is_ascii_lowercase()) && input.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err(invalid("it mixes upper and lower case"));
+    }
+    let input = input.to_lowercase();
+    let (hrp, chars) = input.rsplit_once('1').ok_or_else(|| invalid("it has no separator"))?;
+    if hrp.is_empty() || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return Err(EncodeError::InvalidHrp(String::from(hrp)));
+    }
+    if chars.len() < 6 {
+        return Err(invalid("it is too short to contain a checksum"));
+    }
+    let values = chars.bytes()
+        .map(|c| BECH32_CHARSET.iter().position(|x| *x == c).map(|p| p as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| invalid("it contains a character outside the bech32 alphabet"))?;
+
+    let mut checksum_input = bech32_hrp_expand(hrp);
+    checksum_input.extend_from_slice(&values);
+    let bech32m = match bech32_polymod(&checksum_input) {
+        BECH32_CONST => false,
+        BECH32M_CONST => true,
+        _ => return Err(invalid("the checksum does not match")),
+    };
+    let data = convert_bits(&values[..values.len() - 6], 5, 8, false)
+        .ok_or_else(|| invalid("its data has invalid padding"))?;
+    Ok((String::from(hrp), data, bech32m))
+}
+
+fn execute_bech32_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let input = matches.value_of("input").unwrap();
+    let variant = matches.value_of("variant");
+
+    if matches.is_present("decode") {
+        let input = String::from_utf8(read_raw_input(input)?)?;
+        let (hrp, data, bech32m) = bech32_decode(input.trim())?;
+        let found = if bech32m { "bech32m" } else { "bech32" };
+        if variant.is_some_and(|v| v != found) {
+            return Err(EncodeError::InvalidBech32(format!("expected {} but found a {} checksum", variant.unwrap(), found)).into());
+        }
+        return Ok(format!("HRP: {}\nVariant: {}\nData: {}", hrp, found, encode_hex(&data)));
+    }
+
+    let data = read_hex_input(input)?;
+    Ok(bech32_encode(matches.value_of("hrp").unwrap(), &data, variant == Some("bech32m"))?)
+}
+
+fn execute_binary_cmd(input: &str, decode: bool) -> util::Res<String> {
+    if !decode {
+        let bytes: Vec<String> = read_hex_input(input)?.iter().map(|b| format!("{:08b}", b)).collect();
+        return Ok(bytes.join(" "));
+    }
+
+    let input = String::from_utf8(read_raw_input(input)?)?;
+    let bits: String = input.trim().trim_start_matches("0b").chars().filter(|c| !c.is_whitespace() && *c != '_').collect();
+    if bits.is_empty() || !bits.chars().all(|c| c == '0' || c == '1') {
+        return Err(CmdError::InvalidInput(format!("{} is not a bit string", input.trim())).into());
+    }
+    let padded = format!("{:0>width$}", bits, width = bits.len().div_ceil(8) * 8);
+    let bytes: Vec<u8> = padded.as_bytes().chunks(8)
+        .map(|byte| byte.iter().fold(0, |acc, bit| acc << 1 | (bit - b'0')))
+        .collect();
+    Ok(encode_hex(&bytes))
+}
+
+fn execute_to_hex_num_cmd(number: &str, pad: Option<&str>) -> util::Res<String> {
+    let n = BigUint::parse_bytes(number.as_bytes(), 10)
+        .ok_or_else(|| CmdError::InvalidInput(format!("{} is not a non-negative decimal integer", number)))?;
+    let digits = format!("{:x}", n);
+    match pad {
+        Some(pad) => {
+            let width = Some(pad.parse::<usize>()?)
+                .filter(|pad| *pad <= MAX_HEX_NUM_PAD)
+                .and_then(|pad| pad.checked_mul(2))
+                .ok_or_else(|| CmdError::InvalidArgument(format!("pad must be at most {} bytes", MAX_HEX_NUM_PAD)))?;
+            if digits.len() > width {
+                return Err(CmdError::InvalidInput(format!("{} does not fit in {} bytes", number, pad)).into());
+            }
+            Ok(format!("0x{}{}", "0".repeat(width - digits.len()), digits))
+        }
+        None => Ok(format!("0x{}", digits)),
+    }
+}
+
+fn execute_from_hex_num_cmd(number: &str) -> util::Res<String> {
+    let n = number.strip_prefix("0x").and_then(|hex| BigUint::parse_bytes(hex.as_bytes(), 16))
+        .ok_or_else(|| CmdError::InvalidInput(format!("{} is not a 0x-prefixed hex integer", number)))?;
+    Ok(n.to_string())
+}
+
+fn execute_swap_endian_cmd(input: &str, chunk: Option<&str>) -> util::Res<String> {
+    let mut data = read_hex_input(input)?;
+    match chunk {
+        Some(chunk) => {
+            let size = chunk.parse::<usize>()?;
+            if size == 0 || !data.len().is_multiple_of(size) {
+                return Err(CmdError::InvalidInput(format!("{} bytes cannot be split into {}-byte chunks", data.len(), chunk)).into());
+            }
+            data.chunks_mut(size).for_each(|c| c.reverse());
+        }
+        None => data.reverse(),
+    }
+    Ok(encode_hex(&data))
+}
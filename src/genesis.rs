@@ -0,0 +1,302 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::{decode_hex, encode_hex, CmdError};
+use crate::crypto::keccak256;
+use crate::{rlp, trie};
+use crypto::sha2::Sha256;
+use crypto::digest::Digest;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use serde_json::{Map, Value};
+use std::{error, fmt, fs};
+
+const GENESIS_DIFFICULTY: u64 = 131_072;
+const GENESIS_GAS_LIMIT: u64 = 4_712_388;
+const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+
+#[derive(Debug)]
+pub enum GenesisError {
+    InvalidJSON(String),
+    InvalidField(String),
+    InvalidAddress(String),
+}
+
+impl fmt::Display for GenesisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            GenesisError::InvalidJSON(e) => write!(f, "invalid genesis file: {}", e),
+            GenesisError::InvalidField(name) => write!(f, "invalid value for genesis field {}", name),
+            GenesisError::InvalidAddress(a) => write!(f, "{} is an invalid address", a),
+        }
+    }
+}
+
+impl error::Error for GenesisError {}
+
+/// Parses a quantity that geth accepts as a hex string, decimal string, or JSON number.
+fn parse_quantity(v: &Value, name: &str) -> Result<BigUint, GenesisError> {
+    let err = || GenesisError::InvalidField(String::from(name));
+    match v {
+        Value::Null => Ok(BigUint::zero()),
+        Value::Number(n) => n.as_u64().map(BigUint::from).ok_or_else(err),
+        Value::String(s) if s.starts_with("0x") => {
+            let digits = s.trim_start_matches("0x");
+            if digits.is_empty() {
+                Ok(BigUint::zero())
+            } else {
+                BigUint::parse_bytes(digits.as_bytes(), 16).ok_or_else(err)
+            }
+        }
+        Value::String(s) => BigUint::parse_bytes(s.as_bytes(), 10).ok_or_else(err),
+        _ => Err(err()),
+    }
+}
+
+fn parse_data(v: &Value, name: &str) -> Result<Vec<u8>, GenesisError> {
+    match v {
+        Value::Null => Ok(Vec::new()),
+        Value::String(s) => decode_hex(s).map_err(|_| GenesisError::InvalidField(String::from(name))),
+        _ => Err(GenesisError::InvalidField(String::from(name))),
+    }
+}
+
+fn parse_fixed(v: &Value, name: &str, size: usize) -> Result<Vec<u8>, GenesisError> {
+    let data = parse_data(v, name)?;
+    if data.len() > size {
+        return Err(GenesisError::InvalidField(String::from(name)));
+    }
+    Ok(left_pad(&data, size))
+}
+
+fn left_pad(data: &[u8], size: usize) -> Vec<u8> {
+    let mut out = vec![0; size - data.len()];
+    out.extend_from_slice(data);
+    out
+}
+
+fn parse_address(s: &str) -> Result<Vec<u8>, GenesisError> {
+    match decode_hex(s) {
+        Ok(ref a) if a.len() == 20 => Ok(a.clone()),
+        _ => Err(GenesisError::InvalidAddress(String::from(s))),
+    }
+}
+
+fn storage_root(storage: &Value) -> Result<[u8; 32], GenesisError> {
+    let slots = match storage.as_object() {
+        Some(slots) => slots,
+        None => return Ok(trie::EMPTY_TRIE_ROOT),
+    };
+
+    let mut entries = Vec::new();
+    for (slot, value) in slots {
+        let key = parse_fixed(&Value::String(slot.clone()), "storage", 32)?;
+        let value = parse_fixed(value, "storage", 32)?;
+        let trimmed: Vec<u8> = value.into_iter().skip_while(|b| *b == 0).collect();
+        if trimmed.is_empty() {
+            continue;
+        }
+        entries.push((keccak256(&key).to_vec(), rlp::encode_bytes(&trimmed)));
+    }
+    Ok(trie::trie_root(&entries))
+}
+
+/// Computes the state root of a genesis `alloc` section.
+fn state_root(alloc: &Value) -> Result<[u8; 32], GenesisError> {
+    let accounts = match alloc.as_object() {
+        Some(accounts) => accounts,
+        None => return Ok(trie::EMPTY_TRIE_ROOT),
+    };
+
+    let mut entries = Vec::new();
+    for (addr, account) in accounts {
+        let address = parse_address(addr)?;
+        let code = parse_data(&account["code"], "code")?;
+        let encoded = rlp::encode_list(&[
+            rlp::encode_uint(&parse_quantity(&account["nonce"], "nonce")?),
+            rlp::encode_uint(&parse_quantity(&account["balance"], "balance")?),
+            rlp::encode_bytes(&storage_root(&account["storage"])?),
+            rlp::encode_bytes(&keccak256(&code)),
+        ]);
+        entries.push((keccak256(&address).to_vec(), encoded));
+    }
+    Ok(trie::trie_root(&entries))
+}
+
+/// Reports whether a fork configured at a block number or timestamp is active at genesis.
+fn fork_active(config: &Value, key: &str, at: &BigUint) -> Result<bool, GenesisError> {
+    match &config[key] {
+        Value::Null => Ok(false),
+        v => Ok(parse_quantity(v, key)? <= *at),
+    }
+}
+
+/// Builds the RLP-encoded genesis block header following geth's defaults.
+fn genesis_header(genesis: &Value) -> Result<(Vec<u8>, [u8; 32]), GenesisError> {
+    let root = state_root(&genesis["alloc"])?;
+    let number = parse_quantity(&genesis["number"], "number")?;
+    let timestamp = parse_quantity(&genesis["timestamp"], "timestamp")?;
+    let mix_hash = parse_fixed(&genesis["mixHash"], "mixHash", 32)?;
+
+    let mut difficulty = parse_quantity(&genesis["difficulty"], "difficulty")?;
+    if genesis["difficulty"].is_null() && mix_hash.iter().all(|b| *b == 0) {
+        difficulty = BigUint::from(GENESIS_DIFFICULTY);
+    }
+    let mut gas_limit = parse_quantity(&genesis["gasLimit"], "gasLimit")?;
+    if gas_limit.is_zero() {
+        gas_limit = BigUint::from(GENESIS_GAS_LIMIT);
+    }
+
+    let mut fields = vec![
+        rlp::encode_bytes(&parse_fixed(&genesis["parentHash"], "parentHash", 32)?),
+        rlp::encode_bytes(&keccak256(&rlp::encode_list(&[]))),
+        rlp::encode_bytes(&parse_fixed(&genesis["coinbase"], "coinbase", 20)?),
+        rlp::encode_bytes(&root),
+        rlp::encode_bytes(&trie::EMPTY_TRIE_ROOT),
+        rlp::encode_bytes(&trie::EMPTY_TRIE_ROOT),
+        rlp::encode_bytes(&[0; 256]),
+        rlp::encode_uint(&difficulty),
+        rlp::encode_uint(&number),
+        rlp::encode_uint(&gas_limit),
+        rlp::encode_uint(&parse_quantity(&genesis["gasUsed"], "gasUsed")?),
+        rlp::encode_uint(&timestamp),
+        rlp::encode_bytes(&parse_data(&genesis["extraData"], "extraData")?),
+        rlp::encode_bytes(&mix_hash),
+        rlp::encode_bytes(&left_pad(&parse_quantity(&genesis["nonce"], "nonce")?.to_bytes_be(), 8)),
+    ];
+
+    let config = &genesis["config"];
+    if fork_active(config, "londonBlock", &number)? {
+        let base_fee = match &genesis["baseFeePerGas"] {
+            Value::Null => BigUint::from(INITIAL_BASE_FEE),
+            v => parse_quantity(v, "baseFeePerGas")?,
+        };
+        fields.push(rlp::encode_uint(&base_fee));
+    }
+    if fork_active(config, "shanghaiTime", &timestamp)? {
+        fields.push(rlp::encode_bytes(&trie::EMPTY_TRIE_ROOT));
+    }
+    if fork_active(config, "cancunTime", &timestamp)? {
+        fields.push(rlp::encode_uint(&parse_quantity(&genesis["blobGasUsed"], "blobGasUsed")?));
+        fields.push(rlp::encode_uint(&parse_quantity(&genesis["excessBlobGas"], "excessBlobGas")?));
+        fields.push(rlp::encode_bytes(&[0; 32]));
+    }
+    if fork_active(config, "pragueTime", &timestamp)? {
+        let mut hasher = Sha256::new();
+        let mut requests_hash: [u8; 32] = [0; 32];
+        hasher.result(&mut requests_hash);
+        fields.push(rlp::encode_bytes(&requests_hash));
+    }
+
+    Ok((rlp::encode_list(&fields), root))
+}
+
+fn read_genesis(path: &str) -> util::Res<Value> {
+    let data = fs::read_to_string(path)?;
+    let genesis: Value = serde_json::from_str(&data).map_err(|e| GenesisError::InvalidJSON(e.to_string()))?;
+    if !genesis.is_object() {
+        return Err(GenesisError::InvalidJSON(String::from("expected a JSON object")).into());
+    }
+    Ok(genesis)
+}
+
+pub fn make_genesis_cmd<'a, 'b>() -> App<'a, 'b> {
+    let hash_cmd = SubCommand::with_name("hash")
+        .arg(Arg::with_name("genesis")
+            .help("path to a geth-style genesis.json")
+            .index(1)
+            .required(true))
+        .about("Computes the genesis block hash and state root of a genesis spec");
+    let add_account_cmd = SubCommand::with_name("add-account")
+        .arg(Arg::with_name("genesis")
+            .help("path to a geth-style genesis.json to update in place")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("address")
+            .short("-a")
+            .required(true)
+            .takes_value(true)
+            .help("The address to prefund."))
+        .arg(Arg::with_name("balance")
+            .short("-b")
+            .required(true)
+            .takes_value(true)
+            .help("The balance in wei, as a decimal or 0x-prefixed hex number."))
+        .about("Adds a prefunded account to the alloc section of a genesis spec");
+
+    SubCommand::with_name("genesis")
+        .subcommand(hash_cmd)
+        .subcommand(add_account_cmd)
+        .about("Build and verify genesis files for private networks.")
+}
+
+pub fn execute_genesis_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("hash", Some(sub)) => execute_hash_cmd(sub.value_of("genesis").unwrap()),
+        ("add-account", Some(sub)) => execute_add_account_cmd(
+            sub.value_of("genesis").unwrap(),
+            sub.value_of("address").unwrap(),
+            sub.value_of("balance").unwrap(),
+        ),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_hash_cmd(path: &str) -> util::Res<String> {
+    let genesis = read_genesis(path)?;
+    let (header, root) = genesis_header(&genesis)?;
+    Ok(format!("Block hash: {}\nState root: {}", encode_hex(&keccak256(&header)), encode_hex(&root)))
+}
+
+fn execute_add_account_cmd(path: &str, address: &str, balance: &str) -> util::Res<String> {
+    let addr = parse_address(address)?;
+    let balance = parse_quantity(&Value::String(String::from(balance)), "balance")?;
+
+    let mut genesis = read_genesis(path)?;
+    let alloc = genesis.as_object_mut().unwrap()
+        .entry("alloc")
+        .or_insert_with(|| Value::Object(Map::new()));
+    let accounts = alloc.as_object_mut()
+        .ok_or_else(|| GenesisError::InvalidField(String::from("alloc")))?;
+
+    let key = hex::encode(&addr);
+    let existing = accounts.keys()
+        .find(|k| decode_hex(k).map(|a| a == addr).unwrap_or(false))
+        .cloned();
+    let mut account = match existing.and_then(|k| accounts.remove(&k)) {
+        Some(Value::Object(account)) => account,
+        Some(_) => return Err(GenesisError::InvalidField(format!("alloc.{}", key)).into()),
+        None => Map::new(),
+    };
+    account.insert(String::from("balance"), Value::String(format!("0x{}", balance.to_str_radix(16))));
+    accounts.insert(key, Value::Object(account));
+
+    let out = serde_json::to_string_pretty(&genesis).map_err(|e| GenesisError::InvalidJSON(e.to_string()))?;
+    fs::write(path, out + "\n")?;
+    Ok(format!("{} prefunded with {} wei", encode_hex(&addr), balance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The custom genesis from go-ethereum's core/genesis_test.go, whose hash
+    /// geth pins as customghash.
+    #[test]
+    fn genesis_hash_matches_geth() {
+        let genesis = json!({
+            "config": { "homesteadBlock": 3 },
+            "alloc": {
+                "0x0100000000000000000000000000000000000000": {
+                    "balance": "0x1",
+                    "storage": {
+                        "0x0100000000000000000000000000000000000000000000000000000000000000":
+                            "0x0100000000000000000000000000000000000000000000000000000000000000"
+                    }
+                }
+            }
+        });
+        let (header, _) = genesis_header(&genesis).unwrap();
+        assert_eq!(hex::encode(keccak256(&header)), "89c99d90b79719238d2645c7642f2c9295246e80775b38cfd162b696817fbd50");
+    }
+}
@@ -9,6 +9,15 @@ pub mod util;
 pub mod encode;
 pub mod units;
 pub mod address;
+pub mod beacon;
+pub mod bench;
+pub mod bls;
+pub mod chain;
+pub mod genesis;
+pub mod rlp;
+pub mod rpc;
+pub mod trie;
+pub mod tx;
 
 fn main() {
     let matches = App::new("ethtool")
@@ -20,6 +29,13 @@ fn main() {
         .subcommand(encode::make_encode_cmd())
         .subcommand(units::make_units_cmd())
         .subcommand(address::make_address_cmd())
+        .subcommand(bench::make_bench_cmd())
+        .subcommand(bls::make_bls_cmd())
+        .subcommand(beacon::make_beacon_cmd())
+        .subcommand(chain::make_chain_cmd())
+        .subcommand(genesis::make_genesis_cmd())
+        .subcommand(tx::make_tx_cmd())
+        .subcommand(rpc::make_rpc_cmd())
         .get_matches();
 
     let res = match matches.subcommand() {
@@ -28,6 +44,13 @@ fn main() {
         ("encode", Some(sub)) => encode::execute_encode_cmd(sub),
         ("units", Some(sub)) => units::execute_units_cmd(sub),
         ("address", Some(sub)) => address::execute_address_cmd(sub),
+        ("bench", Some(sub)) => bench::execute_bench_cmd(sub),
+        ("bls", Some(sub)) => bls::execute_bls_cmd(sub),
+        ("beacon", Some(sub)) => beacon::execute_beacon_cmd(sub),
+        ("chain", Some(sub)) => chain::execute_chain_cmd(sub),
+        ("genesis", Some(sub)) => genesis::execute_genesis_cmd(sub),
+        ("tx", Some(sub)) => tx::execute_tx_cmd(sub),
+        ("rpc", Some(sub)) => rpc::execute_rpc_cmd(sub),
         _ => {
             println!("invalid subcommand");
             process::exit(1)
@@ -35,7 +58,12 @@ fn main() {
     };
 
     match res {
+        // Commands that stream their output to stdout return nothing here.
+        Ok(out) if out.is_empty() => {}
         Ok(out) => println!("{}", out),
-        Err(e) => println!("error: {}", e)
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1)
+        }
     }
 }
@@ -0,0 +1,46 @@
+use num_bigint::BigUint;
+
+fn encode_length(len: usize, offset: u8) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+
+    let len_bytes: Vec<u8> = (len as u64).to_be_bytes().iter()
+        .skip_while(|b| **b == 0)
+        .cloned()
+        .collect();
+    let mut out = vec![offset + 55 + len_bytes.len() as u8];
+    out.extend(len_bytes);
+    out
+}
+
+pub fn encode_bytes(data: &[u8]) -> Vec<u8> {
+    if data.len() == 1 && data[0] < 0x80 {
+        return data.to_vec();
+    }
+
+    let mut out = encode_length(data.len(), 0x80);
+    out.extend_from_slice(data);
+    out
+}
+
+pub fn encode_uint(num: &BigUint) -> Vec<u8> {
+    let b = num.to_bytes_be();
+    if b == [0] {
+        encode_bytes(&[])
+    } else {
+        encode_bytes(&b)
+    }
+}
+
+pub fn encode_u64(num: u64) -> Vec<u8> {
+    encode_uint(&BigUint::from(num))
+}
+
+/// Encodes a list whose items have already been RLP-encoded.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload: Vec<u8> = items.concat();
+    let mut out = encode_length(payload.len(), 0xc0);
+    out.extend(payload);
+    out
+}
@@ -0,0 +1,142 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::{decode_hex, encode_hex, CmdError};
+use crate::address::address_label;
+use serde_json::{json, Map, Value};
+use std::{error, fmt};
+
+#[derive(Debug)]
+pub enum RpcError {
+    Remote(i64, String),
+    InvalidResponse(String),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            RpcError::Remote(code, msg) => write!(f, "RPC error {}: {}", code, msg),
+            RpcError::InvalidResponse(s) => write!(f, "invalid RPC response: {}", s),
+        }
+    }
+}
+
+impl error::Error for RpcError {}
+
+/// Performs a JSON-RPC call and returns its `result`.
+pub fn call(url: &str, method: &str, params: Value) -> util::Res<Value> {
+    let req = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+    let mut res: Value = ureq::post(url).send_json(req)?.into_json()?;
+
+    if let Some(err) = res.get("error") {
+        let code = err["code"].as_i64().unwrap_or(0);
+        let msg = err["message"].as_str().unwrap_or("unknown error");
+        return Err(RpcError::Remote(code, String::from(msg)).into());
+    }
+    match res.get_mut("result") {
+        Some(result) => Ok(result.take()),
+        None => Err(RpcError::InvalidResponse(String::from("missing result")).into()),
+    }
+}
+
+/// Parses a hex quantity such as those returned by `eth_estimateGas`.
+pub fn parse_quantity(v: &Value) -> Result<u64, RpcError> {
+    v.as_str()
+        .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+        .ok_or_else(|| RpcError::InvalidResponse(format!("{} is not a quantity", v)))
+}
+
+pub fn make_rpc_url_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("rpc-url")
+        .long("rpc-url")
+        .takes_value(true)
+        .required(true)
+        .env("ETH_RPC_URL")
+        .help("The JSON-RPC endpoint to query.")
+}
+
+pub fn make_rpc_cmd<'a, 'b>() -> App<'a, 'b> {
+    let create_access_list_cmd = SubCommand::with_name("create-access-list")
+        .arg(make_rpc_url_arg())
+        .arg(Arg::with_name("from")
+            .long("from")
+            .takes_value(true)
+            .help("The sender of the prospective transaction."))
+        .arg(Arg::with_name("to")
+            .long("to")
+            .takes_value(true)
+            .required(true)
+            .help("The recipient of the prospective transaction."))
+        .arg(Arg::with_name("data")
+            .short("-d")
+            .long("data")
+            .takes_value(true)
+            .help("The hex-encoded calldata of the prospective transaction."))
+        .arg(Arg::with_name("value")
+            .long("value")
+            .takes_value(true)
+            .help("The value to send in wei, as a 0x-prefixed hex quantity."))
+        .arg(Arg::with_name("block")
+            .long("block")
+            .takes_value(true)
+            .default_value("latest")
+            .help("The block to simulate against."))
+        .arg(Arg::with_name("tx-type")
+            .long("tx-type")
+            .takes_value(true)
+            .possible_values(&["2930", "1559"])
+            .help("Prints the transaction request with the access list attached, as an EIP-2930 or EIP-1559 transaction."))
+        .about("Generates an access list for a transaction with eth_createAccessList");
+
+    SubCommand::with_name("rpc")
+        .subcommand(create_access_list_cmd)
+        .about("Query Ethereum nodes over JSON-RPC.")
+}
+
+pub fn execute_rpc_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("create-access-list", Some(sub)) => execute_create_access_list_cmd(sub),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_create_access_list_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let url = matches.value_of("rpc-url").unwrap();
+    let block = matches.value_of("block").unwrap();
+
+    let mut tx = Map::new();
+    for field in &["from", "to", "data", "value"] {
+        if let Some(v) = matches.value_of(field) {
+            tx.insert(String::from(*field), Value::String(String::from(v)));
+        }
+    }
+
+    let res = call(url, "eth_createAccessList", json!([tx, block]))?;
+    let access_list = res["accessList"].clone();
+    let gas_without = parse_quantity(&call(url, "eth_estimateGas", json!([tx, block]))?)?;
+    tx.insert(String::from("accessList"), access_list.clone());
+    let gas_with = parse_quantity(&call(url, "eth_estimateGas", json!([tx, block]))?)?;
+
+    if let Some(tx_type) = matches.value_of("tx-type") {
+        let type_id = if tx_type == "2930" { "0x1" } else { "0x2" };
+        tx.insert(String::from("type"), Value::String(String::from(type_id)));
+        tx.insert(String::from("gas"), Value::String(format!("0x{:x}", gas_with)));
+        return Ok(serde_json::to_string_pretty(&tx)?);
+    }
+
+    let mut out = format!("{}\nGas with access list: {}\nGas without access list: {}\nDifference: {}",
+                          serde_json::to_string_pretty(&access_list)?,
+                          gas_with, gas_without, gas_without as i64 - gas_with as i64);
+    let labeled: Vec<String> = access_list.as_array().into_iter().flatten()
+        .filter_map(|entry| entry["address"].as_str().and_then(|a| decode_hex(a).ok()))
+        .filter_map(|addr| address_label(&addr).map(|label| format!("{} ({})", encode_hex(&addr), label)))
+        .collect();
+    if !labeled.is_empty() {
+        out.push_str(&format!("\nLabeled addresses: {}", labeled.join(", ")));
+    }
+    Ok(out)
+}
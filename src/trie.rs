@@ -0,0 +1,118 @@
+use crate::crypto::keccak256;
+use crate::rlp;
+
+pub const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+fn to_nibbles(key: &[u8]) -> Vec<u8> {
+    key.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect()
+}
+
+/// Hex-prefix encodes a nibble path, flagging whether it terminates in a leaf.
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 2 } else { 0 };
+    let mut out = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if nibbles.len() % 2 == 1 {
+        out.push(((flag + 1) << 4) | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        out.push(flag << 4);
+        nibbles
+    };
+
+    for pair in rest.chunks(2) {
+        out.push((pair[0] << 4) | pair[1]);
+    }
+    out
+}
+
+/// Returns how a child node is referenced from its parent: inline if its
+/// encoding is shorter than 32 bytes, by hash otherwise.
+fn node_ref(node: Vec<u8>) -> Vec<u8> {
+    if node.len() < 32 {
+        node
+    } else {
+        rlp::encode_bytes(&keccak256(&node))
+    }
+}
+
+fn common_prefix_len(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> usize {
+    let first = &items[0].0;
+    let mut len = 0;
+    while depth + len < first.len() && items.iter().all(|(k, _)| k.len() > depth + len && k[depth + len] == first[depth + len]) {
+        len += 1;
+    }
+    len
+}
+
+fn encode_node(items: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Vec<u8> {
+    if items.len() == 1 {
+        let (key, value) = &items[0];
+        return rlp::encode_list(&[rlp::encode_bytes(&hex_prefix(&key[depth..], true)), rlp::encode_bytes(value)]);
+    }
+
+    let prefix = common_prefix_len(items, depth);
+    if prefix > 0 {
+        let path = hex_prefix(&items[0].0[depth..depth + prefix], false);
+        let child = encode_node(items, depth + prefix);
+        return rlp::encode_list(&[rlp::encode_bytes(&path), node_ref(child)]);
+    }
+
+    let mut branch: Vec<Vec<u8>> = Vec::with_capacity(17);
+    for nibble in 0..16 {
+        let children: Vec<(Vec<u8>, Vec<u8>)> = items.iter()
+            .filter(|(k, _)| k.len() > depth && k[depth] == nibble)
+            .cloned()
+            .collect();
+        if children.is_empty() {
+            branch.push(rlp::encode_bytes(&[]));
+        } else {
+            branch.push(node_ref(encode_node(&children, depth + 1)));
+        }
+    }
+    match items.iter().find(|(k, _)| k.len() == depth) {
+        Some((_, value)) => branch.push(rlp::encode_bytes(value)),
+        None => branch.push(rlp::encode_bytes(&[])),
+    }
+    rlp::encode_list(&branch)
+}
+
+/// Computes the root hash of a Merkle Patricia trie holding the given key/value pairs.
+pub fn trie_root(entries: &[(Vec<u8>, Vec<u8>)]) -> [u8; 32] {
+    if entries.is_empty() {
+        return EMPTY_TRIE_ROOT;
+    }
+
+    let mut items: Vec<(Vec<u8>, Vec<u8>)> = entries.iter()
+        .map(|(k, v)| (to_nibbles(k), v.clone()))
+        .collect();
+    items.sort();
+    keccak256(&encode_node(&items, 0))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(entries: &[(&str, &str)]) -> String {
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = entries.iter()
+            .map(|(k, v)| (k.as_bytes().to_vec(), v.as_bytes().to_vec()))
+            .collect();
+        hex::encode(trie_root(&entries))
+    }
+
+    /// Vectors from the trieanyorder suite in ethereum/tests.
+    #[test]
+    fn trie_root_matches_reference_vectors() {
+        assert_eq!(root(&[]), hex::encode(EMPTY_TRIE_ROOT));
+        assert_eq!(root(&[("doe", "reindeer"), ("dog", "puppy"), ("dogglesworth", "cat")]),
+                   "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
+        assert_eq!(root(&[("do", "verb"), ("horse", "stallion"), ("doge", "coin"), ("dog", "puppy")]),
+                   "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84");
+        assert_eq!(root(&[("foo", "bar"), ("food", "bass")]),
+                   "17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3");
+    }
+}
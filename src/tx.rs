@@ -0,0 +1,124 @@
+use clap::{App, SubCommand, Arg, ArgMatches};
+use crate::util;
+use crate::util::{make_input_arg, read_hex_input, CmdError};
+
+const TX_BASE_GAS: u64 = 21_000;
+const ZERO_BYTE_GAS: u64 = 4;
+const NONZERO_BYTE_GAS: u64 = 16;
+const FLOOR_GAS_PER_TOKEN: u64 = 10;
+const STANDARD_GAS_PER_TOKEN: u64 = 4;
+
+/// OP Stack chains price L1 data with the Ecotone formula; these are the
+/// published fee scalars for the two largest deployments.
+const OP_STACK_CHAINS: &[(&str, u64, u64)] = &[
+    ("OP Mainnet", 5_227, 1_014_213),
+    ("Base", 2_269, 1_055_762),
+];
+
+struct CalldataStats {
+    len: u64,
+    zero: u64,
+    nonzero: u64,
+    trailing_zero: u64,
+}
+
+impl CalldataStats {
+    fn new(data: &[u8]) -> CalldataStats {
+        let zero = data.iter().filter(|b| **b == 0).count() as u64;
+        CalldataStats {
+            len: data.len() as u64,
+            zero,
+            nonzero: data.len() as u64 - zero,
+            trailing_zero: data.iter().rev().take_while(|b| **b == 0).count() as u64,
+        }
+    }
+
+    /// Calldata gas under EIP-2028.
+    fn gas(&self) -> u64 {
+        self.zero * ZERO_BYTE_GAS + self.nonzero * NONZERO_BYTE_GAS
+    }
+
+    /// Calldata tokens as defined by EIP-7623.
+    fn tokens(&self) -> u64 {
+        self.zero + self.nonzero * 4
+    }
+}
+
+pub fn make_tx_cmd<'a, 'b>() -> App<'a, 'b> {
+    let calldata_cost_cmd = SubCommand::with_name("calldata-cost")
+        .arg(make_input_arg("The hex-encoded calldata to analyze. If - is provided, will read from stdin"))
+        .arg(Arg::with_name("l1-base-fee")
+            .long("l1-base-fee")
+            .takes_value(true)
+            .default_value("10")
+            .help("The L1 base fee in gwei used for L2 data cost estimates."))
+        .arg(Arg::with_name("blob-base-fee")
+            .long("blob-base-fee")
+            .takes_value(true)
+            .default_value("1")
+            .help("The L1 blob base fee in wei used for L2 data cost estimates."))
+        .about("Reports the gas cost of calldata on L1 and estimated data costs on major L2s");
+
+    SubCommand::with_name("tx")
+        .subcommand(calldata_cost_cmd)
+        .about("Analyze and build transactions.")
+}
+
+pub fn execute_tx_cmd(matches: &ArgMatches) -> util::Res<String> {
+    match matches.subcommand() {
+        ("calldata-cost", Some(sub)) => execute_calldata_cost_cmd(
+            sub.value_of("input").unwrap(),
+            sub.value_of("l1-base-fee").unwrap(),
+            sub.value_of("blob-base-fee").unwrap(),
+        ),
+        (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
+    }
+}
+
+fn execute_calldata_cost_cmd(input: &str, l1_base_fee: &str, blob_base_fee: &str) -> util::Res<String> {
+    let data = read_hex_input(input)?;
+    let overflow = || CmdError::InvalidArgument(String::from("fees are too large to estimate data costs"));
+    let l1_base_fee = l1_base_fee.parse::<u128>()?.checked_mul(1_000_000_000).ok_or_else(overflow)?;
+    let blob_base_fee: u128 = blob_base_fee.parse()?;
+    let stats = CalldataStats::new(&data);
+
+    let standard = TX_BASE_GAS + stats.tokens() * STANDARD_GAS_PER_TOKEN;
+    let floor = TX_BASE_GAS + stats.tokens() * FLOOR_GAS_PER_TOKEN;
+
+    let mut out = format!("Bytes: {} ({} zero, {} nonzero)\n", stats.len, stats.zero, stats.nonzero);
+    out.push_str(&format!("Calldata gas (EIP-2028): {}\n", stats.gas()));
+    out.push_str(&format!("Intrinsic gas: {}\n", standard));
+    out.push_str(&format!("EIP-7623 floor: {} ({} tokens)\n", floor, stats.tokens()));
+
+    out.push_str("Estimated L1 data cost:");
+    for (name, base_fee_scalar, blob_base_fee_scalar) in OP_STACK_CHAINS {
+        let cost = (16 * *base_fee_scalar as u128).checked_mul(l1_base_fee)
+            .and_then(|l1| l1.checked_add((*blob_base_fee_scalar as u128).checked_mul(blob_base_fee)?))
+            .and_then(|weighted| weighted.checked_mul(stats.gas() as u128))
+            .ok_or_else(overflow)? / 16_000_000;
+        out.push_str(&format!("\n  {}: {} wei", name, cost));
+    }
+    let arbitrum = (stats.len as u128 * 16).checked_mul(l1_base_fee).ok_or_else(overflow)?;
+    out.push_str(&format!("\n  Arbitrum One (uncompressed upper bound): {} wei", arbitrum));
+
+    let mut suggestions = Vec::new();
+    if stats.trailing_zero > 0 {
+        suggestions.push(format!("trimming {} trailing zero bytes would save {} gas if the callee tolerates short calldata",
+                                 stats.trailing_zero, stats.trailing_zero * ZERO_BYTE_GAS));
+    }
+    if stats.tokens() > 0 {
+        suggestions.push(format!("the EIP-7623 floor applies unless execution uses at least {} gas",
+                                 floor - standard));
+    }
+    if stats.nonzero > stats.zero && stats.len >= 64 {
+        suggestions.push(String::from("calldata is mostly nonzero; compressing arguments or packing small values may reduce cost"));
+    }
+    if !suggestions.is_empty() {
+        out.push_str("\nSuggestions:");
+        for s in suggestions {
+            out.push_str(&format!("\n  - {}", s));
+        }
+    }
+
+    Ok(out)
+}
@@ -1,20 +1,45 @@
-use clap::{App, SubCommand, Arg, ArgMatches};
+use clap::{App, AppSettings, SubCommand, Arg, ArgMatches};
 use crate::util;
-use crate::util::CmdError;
+use crate::util::{decode_hex, CmdError};
+use crate::rpc::{self, make_rpc_url_arg, RpcError};
+use serde_json::{json, Value};
 use std::error;
 use std::fmt;
-use std::str::FromStr;
-use rust_decimal::Decimal;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{pow, One, Signed, Zero};
+
+const COINGECKO_PRICE_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=";
+/// The Chainlink ETH / USD price feed on mainnet.
+const CHAINLINK_ETH_USD_FEED: &str = "0x5f4eC3Df9cbd43714FE2740f5E3616155c5b8419";
+/// The selectors of Chainlink's `latestRoundData()` and `decimals()`.
+const LATEST_ROUND_DATA_SELECTOR: &str = "0xfeaf968c";
+const DECIMALS_SELECTOR: &str = "0x313ce567";
+/// Fiat values are rounded to this many places unless --precision is given.
+const DEFAULT_FIAT_PRECISION: usize = 2;
+/// Plain-number results of `units math` are rounded to this many places.
+const DEFAULT_MATH_PRECISION: usize = 18;
+/// The number of decimal digits in 2^256 - 1.
+pub const MAX_UINT256_DIGITS: usize = 78;
 
 #[derive(Debug)]
 pub enum UnitError {
-    InvalidUnit(String)
+    InvalidUnit(String),
+    InvalidAmount(String),
+    InvalidExpression(String),
+    Overflow(String),
+    PrecisionLoss(String, usize),
+    NegativeAmount(String),
 }
 
 impl fmt::Display for UnitError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            UnitError::InvalidUnit(u) => write!(f, "invalid unit: {}", u)
+            UnitError::InvalidUnit(u) => write!(f, "invalid unit: {}", u),
+            UnitError::InvalidAmount(a) => write!(f, "invalid amount: {}", a),
+            UnitError::InvalidExpression(e) => write!(f, "invalid expression: {}", e),
+            UnitError::Overflow(a) => write!(f, "{} does not fit in a uint256", a),
+            UnitError::NegativeAmount(a) => write!(f, "{} is negative; pass --allow-negative to convert signed amounts", a),
+            UnitError::PrecisionLoss(a, decimals) => write!(f, "{} has more than {} decimal places and cannot be converted exactly", a, decimals),
         }
     }
 }
@@ -29,83 +54,697 @@ pub enum Unit {
     Microether,
     Milliether,
     Ether,
+    Kether,
+    Mether,
+    Gether,
+    Tether,
 }
 
 impl Unit {
-    fn convert_to_wei(&self, input: Decimal) -> Decimal {
-        let res = match &self {
-            Unit::Wei => Some(input),
-            Unit::Kwei => input.checked_mul(Decimal::new(1e3 as i64, 0)),
-            Unit::Mwei => input.checked_mul(Decimal::new(1e6 as i64, 0)),
-            Unit::Gwei => input.checked_mul(Decimal::new(1e9 as i64, 0)),
-            Unit::Microether => input.checked_mul(Decimal::new(1e12 as i64, 0)),
-            Unit::Milliether => input.checked_mul(Decimal::new(1e15 as i64, 0)),
-            Unit::Ether => input.checked_mul(Decimal::new(1e18 as i64, 0)),
-        };
-        
-        res.unwrap()
-    }
-
-    fn convert_from_wei(&self, input: Decimal) -> Decimal {
-        let res = match &self {
-            Unit::Wei => Some(input),
-            Unit::Kwei => input.checked_div(Decimal::new(1e3 as i64, 18)),
-            Unit::Mwei => input.checked_div(Decimal::new(1e6 as i64, 18)),
-            Unit::Gwei => input.checked_div(Decimal::new(1e9 as i64, 18)),
-            Unit::Microether => input.checked_div(Decimal::new(1e12 as i64, 18)),
-            Unit::Milliether => input.checked_div(Decimal::new(1e15 as i64, 18)),
-            Unit::Ether => input.checked_div(Decimal::new(1e18 as i64, 18)),
-        };
+    /// The number of decimal places between this unit and wei.
+    fn decimals(&self) -> usize {
+        match &self {
+            Unit::Wei => 0,
+            Unit::Kwei => 3,
+            Unit::Mwei => 6,
+            Unit::Gwei => 9,
+            Unit::Microether => 12,
+            Unit::Milliether => 15,
+            Unit::Ether => 18,
+            Unit::Kether => 21,
+            Unit::Mether => 24,
+            Unit::Gether => 27,
+            Unit::Tether => 30,
+        }
+    }
+
+    fn convert_to_wei(&self, input: &str, rounding: Option<&Rounding>) -> Result<BigUint, UnitError> {
+        parse_units_rounded(input, self.decimals(), rounding)
+    }
 
-        res.unwrap()
+    fn convert_from_wei(&self, input: &BigUint, precision: Option<usize>, rounding: &Rounding) -> String {
+        format_units_rounded(input, self.decimals(), precision, rounding)
     }
 
     fn possible_values<'a>() -> &'a [&'a str] {
-        &["wei", "kwei", "babbage", "mwei", "lovelace", "gwei", "shannon",
-            "microether", "szabo", "milliether", "finney", "ether", "eth"]
+        &["wei", "kwei", "babbage", "femtoether", "mwei", "lovelace", "picoether",
+            "gwei", "shannon", "nanoether", "microether", "szabo", "milliether", "finney",
+            "ether", "eth", "kether", "grand", "mether", "gether", "tether"]
     }
 
     fn from_str(input: &str) -> Result<Unit, UnitError> {
         match input {
             "wei" => Ok(Unit::Wei),
-            "kwei" | "babbage" => Ok(Unit::Kwei),
-            "mwei" | "lovelace" => Ok(Unit::Mwei),
-            "gwei" | "shannon" => Ok(Unit::Gwei),
+            "kwei" | "babbage" | "femtoether" => Ok(Unit::Kwei),
+            "mwei" | "lovelace" | "picoether" => Ok(Unit::Mwei),
+            "gwei" | "shannon" | "nanoether" => Ok(Unit::Gwei),
             "microether" | "szabo" => Ok(Unit::Microether),
             "milliether" | "finney" => Ok(Unit::Milliether),
             "ether" | "eth" => Ok(Unit::Ether),
+            "kether" | "grand" => Ok(Unit::Kether),
+            "mether" => Ok(Unit::Mether),
+            "gether" => Ok(Unit::Gether),
+            "tether" => Ok(Unit::Tether),
             u => Err(UnitError::InvalidUnit(String::from(u)))
         }
     }
 }
 
+pub enum Rounding {
+    Up,
+    Down,
+    HalfEven,
+}
+
+impl Rounding {
+    fn possible_values<'a>() -> &'a [&'a str] {
+        &["up", "down", "half-even"]
+    }
+
+    fn from_str(input: &str) -> Result<Rounding, UnitError> {
+        match input {
+            "up" => Ok(Rounding::Up),
+            "down" => Ok(Rounding::Down),
+            "half-even" => Ok(Rounding::HalfEven),
+            r => Err(UnitError::InvalidAmount(format!("unknown rounding mode {}", r)))
+        }
+    }
+
+    /// Divides `n` by `10^places`, rounding away the discarded digits.
+    fn round(&self, n: &BigUint, places: usize) -> BigUint {
+        self.divide(n, &pow(BigUint::from(10u32), places))
+    }
+
+    /// Divides `n` by `d`, rounding the remainder.
+    fn divide(&self, n: &BigUint, d: &BigUint) -> BigUint {
+        let (quotient, remainder) = (n / d, n % d);
+        let round_up = match self {
+            Rounding::Up => !remainder.is_zero(),
+            Rounding::Down => false,
+            Rounding::HalfEven => {
+                let twice = remainder * 2u32;
+                twice > *d || (twice == *d && !(&quotient % 2u32).is_zero())
+            }
+        };
+        if round_up { quotient + 1u32 } else { quotient }
+    }
+}
+
+/// Parses a non-negative decimal amount, optionally in scientific notation
+/// such as `2.5e9`, or a 0x-prefixed hex integer, into an integer scaled by
+/// `10^decimals`. Fails rather than rounding, and when the result would not
+/// fit in a uint256.
+pub fn parse_units(amount: &str, decimals: usize) -> Result<BigUint, UnitError> {
+    parse_units_rounded(amount, decimals, None)
+}
+
+/// Like `parse_units`, but rounds away decimal places beyond `decimals` when
+/// given a rounding mode.
+pub fn parse_units_rounded(amount: &str, decimals: usize, rounding: Option<&Rounding>) -> Result<BigUint, UnitError> {
+    let invalid = |reason: &str| UnitError::InvalidAmount(format!("{} {}", amount, reason));
+    if let Some(hex) = amount.strip_prefix("0x") {
+        let n = BigUint::parse_bytes(hex.as_bytes(), 16).ok_or_else(|| invalid("is not a hex quantity"))?;
+        return check_uint256(amount, n * pow(BigUint::from(10u32), decimals));
+    }
+    let (mantissa, exponent) = match amount.find(['e', 'E']) {
+        Some(i) => (&amount[..i], amount[i + 1..].parse::<i64>().map_err(|_| invalid("has an invalid exponent"))?),
+        None => (amount, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int.is_empty() && frac.is_empty() || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid("is not a non-negative decimal number"));
+    }
+
+    let frac = frac.trim_end_matches('0');
+    let digits = format!("{}{}", int, frac);
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Ok(BigUint::zero());
+    }
+    // The amount is `digits * 10^shift` in the target's base units.
+    let shift = exponent.saturating_add(decimals as i64).saturating_sub(frac.len() as i64);
+    let exact: BigUint = digits.parse().unwrap_or_default();
+    if shift >= 0 {
+        if (digits.len() as i64).saturating_add(shift) > MAX_UINT256_DIGITS as i64 {
+            return Err(UnitError::Overflow(String::from(amount)));
+        }
+        return check_uint256(amount, exact * pow(BigUint::from(10u32), shift as usize));
+    }
+
+    let places = shift.unsigned_abs() as usize;
+    match rounding {
+        Some(rounding) if places <= digits.len() => check_uint256(amount, rounding.round(&exact, places)),
+        // The amount is less than a tenth of a base unit.
+        Some(Rounding::Up) => Ok(BigUint::one()),
+        Some(_) => Ok(BigUint::zero()),
+        None => Err(UnitError::PrecisionLoss(String::from(amount), decimals)),
+    }
+}
+
+fn check_uint256(amount: &str, n: BigUint) -> Result<BigUint, UnitError> {
+    if n.bits() > 256 {
+        return Err(UnitError::Overflow(String::from(amount)));
+    }
+    Ok(n)
+}
+
+/// Formats an integer scaled by `10^decimals` as a decimal amount, dropping
+/// trailing zeros after the decimal point.
+pub fn format_units(amount: &BigUint, decimals: usize) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let scale = pow(BigUint::from(10u32), decimals);
+    let int = amount / &scale;
+    let frac = format!("{:0>width$}", (amount % &scale).to_string(), width = decimals);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        int.to_string()
+    } else {
+        format!("{}.{}", int, frac)
+    }
+}
+
+/// Parses a human-entered amount such as `1.5 ether`, `2gwei`, or `0.1 ETH`
+/// into wei. Amounts without a unit are taken to be in wei.
+pub fn parse_amount(input: &str) -> Result<BigUint, UnitError> {
+    let input = input.trim();
+    let split = match input.strip_prefix("0x") {
+        Some(hex) => hex.find(|c: char| !c.is_ascii_hexdigit()).map_or(input.len(), |i| i + 2),
+        None => input.char_indices()
+            .find(|(i, c)| c.is_ascii_alphabetic() && !is_exponent(&input[*i..]))
+            .map_or(input.len(), |(i, _)| i),
+    };
+    let (amount, unit) = (input[..split].trim(), input[split..].trim());
+    if amount.is_empty() {
+        return Err(UnitError::InvalidAmount(format!("{} has no number", input)));
+    }
+    let unit = if unit.is_empty() { Unit::Wei } else { Unit::from_str(&unit.to_lowercase())? };
+    unit.convert_to_wei(amount, None)
+}
+
+/// Whether `s` starts with the exponent of a number in scientific notation.
+fn is_exponent(s: &str) -> bool {
+    let rest = match s.strip_prefix(['e', 'E']) {
+        Some(rest) => rest.trim_start_matches(['-', '+']),
+        None => return false,
+    };
+    rest.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Like `format_units`, but rounds to at most `precision` decimal places.
+pub fn format_units_rounded(amount: &BigUint, decimals: usize, precision: Option<usize>, rounding: &Rounding) -> String {
+    match precision {
+        Some(precision) if precision < decimals => format_units(&rounding.round(amount, decimals - precision), precision),
+        _ => format_units(amount, decimals),
+    }
+}
+
+/// Adds the rounding flags shared by the units commands. Commands producing
+/// fractional output also take a precision.
+fn with_rounding_args<'a, 'b>(cmd: App<'a, 'b>, precision: bool) -> App<'a, 'b> {
+    if precision {
+        cmd.arg(Arg::with_name("precision")
+            .long("precision")
+            .takes_value(true)
+            .help("The maximum number of decimal places to output."))
+            .arg(Arg::with_name("round")
+                .long("round")
+                .takes_value(true)
+                .possible_values(Rounding::possible_values())
+                .default_value("down")
+                .help("How to round away decimal places beyond --precision."))
+    } else {
+        cmd.arg(Arg::with_name("round")
+            .long("round")
+            .takes_value(true)
+            .possible_values(Rounding::possible_values())
+            .help("Rounds amounts with more decimal places than the base unit allows instead of failing."))
+    }
+}
+
+/// An exact rational value in an arithmetic expression. Amounts are in wei;
+/// plain numbers and percentages are dimensionless.
+struct Quantity {
+    num: BigInt,
+    den: BigInt,
+    amount: bool,
+}
+
+impl Quantity {
+    fn new(num: BigInt, den: BigInt, amount: bool) -> Result<Quantity, UnitError> {
+        if den.is_zero() {
+            return Err(UnitError::InvalidExpression(String::from("division by zero")));
+        }
+        let (mut a, mut b) = (num.abs(), den.abs());
+        while !b.is_zero() {
+            let t = &a % &b;
+            a = b;
+            b = t;
+        }
+        let sign = if den.is_negative() { -BigInt::one() } else { BigInt::one() };
+        Ok(Quantity { num: num * &sign / &a, den: den * sign / a, amount })
+    }
+
+    fn apply(self, op: char, rhs: Quantity) -> Result<Quantity, UnitError> {
+        let mismatch = |what: &str| Err(UnitError::InvalidExpression(String::from(what)));
+        match op {
+            '+' | '-' => {
+                if self.amount != rhs.amount {
+                    return mismatch("cannot add or subtract an amount and a plain number; give both a unit");
+                }
+                let rhs_num = if op == '+' { rhs.num } else { -rhs.num };
+                Quantity::new(self.num * &rhs.den + rhs_num * &self.den, self.den * rhs.den, self.amount)
+            }
+            '*' => {
+                if self.amount && rhs.amount {
+                    return mismatch("cannot multiply two amounts");
+                }
+                Quantity::new(self.num * rhs.num, self.den * rhs.den, self.amount || rhs.amount)
+            }
+            _ => {
+                if rhs.amount && !self.amount {
+                    return mismatch("cannot divide a plain number by an amount");
+                }
+                Quantity::new(self.num * rhs.den, self.den * rhs.num, self.amount && !rhs.amount)
+            }
+        }
+    }
+}
+
+/// A recursive-descent evaluator for arithmetic on amounts, e.g.
+/// `1 ether + 21000 * 30 gwei`.
+struct Evaluator<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(expr: &'a str) -> Evaluator<'a> {
+        let mut tokens = Vec::new();
+        let mut start = None;
+        for (i, c) in expr.char_indices() {
+            let is_word = c.is_ascii_alphanumeric() || c == '.';
+            let boundary = match start {
+                Some(s) => !is_word || expr[s..i].chars().last().unwrap().is_ascii_alphabetic() != c.is_ascii_alphabetic(),
+                None => false,
+            };
+            if let (true, Some(s)) = (boundary, start) {
+                tokens.push(&expr[s..i]);
+                start = None;
+            }
+            if is_word && start.is_none() {
+                start = Some(i);
+            } else if !is_word && !c.is_whitespace() {
+                tokens.push(&expr[i..i + c.len_utf8()]);
+            }
+        }
+        if let Some(s) = start {
+            tokens.push(&expr[s..]);
+        }
+        Evaluator { tokens, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<&'a str, UnitError> {
+        let token = self.peek().ok_or_else(|| UnitError::InvalidExpression(String::from("unexpected end of expression")))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn evaluate(mut self) -> Result<Quantity, UnitError> {
+        let value = self.expr()?;
+        match self.peek() {
+            Some(token) => Err(UnitError::InvalidExpression(format!("unexpected {}", token))),
+            None => Ok(value),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Quantity, UnitError> {
+        let mut value = self.term()?;
+        while let Some(op @ ("+" | "-")) = self.peek() {
+            self.pos += 1;
+            value = value.apply(op.chars().next().unwrap(), self.term()?)?;
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<Quantity, UnitError> {
+        let mut value = self.factor()?;
+        while let Some(op @ ("*" | "/")) = self.peek() {
+            self.pos += 1;
+            value = value.apply(op.chars().next().unwrap(), self.factor()?)?;
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<Quantity, UnitError> {
+        match self.next()? {
+            "-" => {
+                let value = self.factor()?;
+                Ok(Quantity { num: -value.num, ..value })
+            }
+            "(" => {
+                let value = self.expr()?;
+                match self.next()? {
+                    ")" => Ok(value),
+                    token => Err(UnitError::InvalidExpression(format!("expected ) but found {}", token))),
+                }
+            }
+            number => {
+                let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+                if int.is_empty() && frac.is_empty() || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+                    return Err(UnitError::InvalidExpression(format!("expected a number but found {}", number)));
+                }
+                let num: BigInt = format!("{}{}", int, frac).parse().unwrap_or_default();
+                let den = pow(BigInt::from(10), frac.len());
+                match self.peek() {
+                    Some("%") => {
+                        self.pos += 1;
+                        Quantity::new(num, den * 100, false)
+                    }
+                    Some(unit) if unit.chars().all(|c| c.is_ascii_alphabetic()) => {
+                        self.pos += 1;
+                        let decimals = Unit::from_str(unit)?.decimals();
+                        Quantity::new(num * pow(BigInt::from(10), decimals), den, true)
+                    }
+                    _ => Quantity::new(num, den, false),
+                }
+            }
+        }
+    }
+}
+
+fn execute_math_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let value = Evaluator::new(matches.value_of("expression").unwrap()).evaluate()?;
+    let (precision, rounding) = parse_rounding_args(matches)?;
+    let rounding = rounding.unwrap_or(Rounding::HalfEven);
+    let sign = if value.num.is_negative() { "-" } else { "" };
+    let num = value.num.abs().to_biguint().unwrap_or_default();
+    let den = value.den.to_biguint().unwrap_or_default();
+
+    if !value.amount {
+        let decimals = precision.unwrap_or(DEFAULT_MATH_PRECISION);
+        let scaled = rounding.divide(&(num * pow(BigUint::from(10u32), decimals)), &den);
+        return Ok(format!("{}{}", sign, format_units(&scaled, decimals)));
+    }
+    let wei = rounding.divide(&num, &den);
+    let unit_str = matches.value_of("unit").unwrap();
+    let unit = Unit::from_str(unit_str)?;
+    let formatted = format!("{}{}", sign, unit.convert_from_wei(&wei, precision, &rounding));
+    Ok(format_display(formatted, Some(unit_str), matches))
+}
+
+/// Fetches the price of one ether in `currency` as a decimal string.
+fn fetch_eth_price(matches: &ArgMatches, currency: &str) -> util::Res<String> {
+    if matches.value_of("source") == Some("chainlink") {
+        let url = matches.value_of("rpc-url").unwrap();
+        let feed = matches.value_of("feed").unwrap();
+        let call = |data: &str| -> util::Res<Vec<u8>> {
+            let res = rpc::call(url, "eth_call", json!([{ "to": feed, "data": data }, "latest"]))?;
+            res.as_str().and_then(|s| decode_hex(s).ok()).filter(|r| r.len() >= 32)
+                .ok_or_else(|| RpcError::InvalidResponse(format!("{} is not ABI-encoded return data", res)).into())
+        };
+        let round = call(LATEST_ROUND_DATA_SELECTOR)?;
+        let decimals = call(DECIMALS_SELECTOR)?;
+        let answer = round.get(32..64).ok_or_else(|| RpcError::InvalidResponse(String::from("latestRoundData returned too little data")))?;
+        if answer[0] & 0x80 != 0 {
+            return Err(RpcError::InvalidResponse(String::from("the feed reported a negative price")).into());
+        }
+        return Ok(format_units(&BigUint::from_bytes_be(answer), decimals[31] as usize));
+    }
+
+    let res = util::http_get_json(&format!("{}{}", COINGECKO_PRICE_URL, currency))?;
+    match &res["ethereum"][currency] {
+        Value::Number(price) => Ok(price.to_string()),
+        _ => Err(UnitError::InvalidAmount(format!("no ETH price in {} was returned", currency)).into()),
+    }
+}
+
+fn execute_to_fiat_cmd(matches: &ArgMatches) -> util::Res<String> {
+    let unit = Unit::from_str(matches.value_of("unit").unwrap())?;
+    let wei = unit.convert_to_wei(matches.value_of("amount").unwrap(), None)?;
+    let currency = matches.value_of("currency").unwrap().to_lowercase();
+    let price = match matches.value_of("at-price") {
+        Some(price) => String::from(price),
+        None => fetch_eth_price(matches, &currency)?,
+    };
+    let price = parse_units_rounded(&price, Unit::Ether.decimals(), Some(&Rounding::HalfEven))?;
+
+    let (precision, rounding) = parse_rounding_args(matches)?;
+    let precision = precision.unwrap_or(DEFAULT_FIAT_PRECISION);
+    let value = format_units_rounded(&(wei * price), 2 * Unit::Ether.decimals(), Some(precision), &rounding.unwrap_or(Rounding::HalfEven));
+    Ok(format_display(value, None, matches) + " " + &currency.to_uppercase())
+}
+
+/// Adds the display flags shared by the units commands.
+fn with_display_args<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.arg(Arg::with_name("pretty")
+        .long("pretty")
+        .conflicts_with("sci")
+        .help("Groups the integer digits with thousands separators and appends the unit, e.g. 1,234.5 ether."))
+        .arg(Arg::with_name("sci")
+            .long("sci")
+            .help("Outputs the amount in scientific notation, e.g. 1.2345e21."))
+}
+
+/// Applies the display flags to a converted decimal amount.
+fn format_display(amount: String, unit: Option<&str>, matches: &ArgMatches) -> String {
+    if !matches.is_present("sci") && !matches.is_present("pretty") {
+        return amount;
+    }
+    let (sign, amount) = match amount.strip_prefix('-') {
+        Some(magnitude) => ("-", magnitude),
+        None => ("", amount.as_str()),
+    };
+    if matches.is_present("sci") {
+        return format!("{}{}", sign, to_scientific(amount));
+    }
+    let (int, frac) = match amount.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (amount, None),
+    };
+    let mut grouped = String::from(sign);
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    if let Some(frac) = frac {
+        grouped = format!("{}.{}", grouped, frac);
+    }
+    match unit {
+        Some(unit) => format!("{} {}", grouped, unit),
+        None => grouped,
+    }
+}
+
+/// Rewrites a non-negative decimal amount in scientific notation, keeping
+/// every significant digit.
+fn to_scientific(amount: &str) -> String {
+    let (int, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    let digits = format!("{}{}", int, frac);
+    let leading_zeros = digits.len() - digits.trim_start_matches('0').len();
+    let significant = digits.trim_start_matches('0').trim_end_matches('0');
+    if significant.is_empty() {
+        return String::from("0");
+    }
+    let exponent = int.len() as i64 - leading_zeros as i64 - 1;
+    let (first, rest) = significant.split_at(1);
+    if rest.is_empty() {
+        format!("{}e{}", first, exponent)
+    } else {
+        format!("{}.{}e{}", first, rest, exponent)
+    }
+}
+
+/// Adds --allow-negative, and lets negative amounts through as values rather
+/// than flags.
+fn with_negative_arg<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.setting(AppSettings::AllowNegativeNumbers)
+        .arg(Arg::with_name("allow-negative")
+            .long("allow-negative")
+            .help("Accepts a negative amount, such as an accounting delta, and outputs a negative result. Rounding applies to the magnitude."))
+}
+
+/// Splits the sign off an amount, rejecting negative amounts unless
+/// --allow-negative was given.
+fn split_sign<'s>(amount: &'s str, matches: &ArgMatches) -> Result<(&'static str, &'s str), UnitError> {
+    match amount.trim().strip_prefix('-') {
+        Some(_) if !matches.is_present("allow-negative") => Err(UnitError::NegativeAmount(String::from(amount))),
+        Some(magnitude) => Ok(("-", magnitude)),
+        None => Ok(("", amount.trim())),
+    }
+}
+
+/// Reattaches a sign to a converted amount, leaving zero unsigned.
+fn with_sign(sign: &str, magnitude: String) -> String {
+    if magnitude.trim_start_matches(['0', 'x']).is_empty() {
+        magnitude
+    } else {
+        format!("{}{}", sign, magnitude)
+    }
+}
+
+fn parse_rounding_args(matches: &ArgMatches) -> util::Res<(Option<usize>, Option<Rounding>)> {
+    let precision = match matches.value_of("precision") {
+        Some(p) => Some(p.parse()?),
+        None => None,
+    };
+    let rounding = match matches.value_of("round") {
+        Some(r) => Some(Rounding::from_str(r)?),
+        None => None,
+    };
+    Ok((precision, rounding))
+}
+
 pub fn make_units_cmd<'a, 'b>() -> App<'a, 'b> {
-    let from_wei_cmd = SubCommand::with_name("from-wei")
+    let from_wei_cmd = with_negative_arg(with_display_args(with_rounding_args(SubCommand::with_name("from-wei")
+        .arg(Arg::with_name("amount")
+            .help("the amount to convert from Wei, in decimal or as a 0x-prefixed hex quantity")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("unit")
+            .help("the output unit")
+            .index(2)
+            .required(true)
+            .possible_values(Unit::possible_values())
+            .default_value("ether"))
+        .about("converts an amount into Ether"), true)));
+    let to_wei_command = with_negative_arg(with_display_args(with_rounding_args(SubCommand::with_name("to-wei")
         .arg(Arg::with_name("amount")
-            .help("the amount to convert from Wei")
+            .help("the amount to convert to Wei, in decimal or as a 0x-prefixed hex integer")
             .index(1)
             .required(true))
         .arg(Arg::with_name("unit")
             .help("the input unit")
             .index(2)
             .required(true)
+            .possible_values(Unit::possible_values())
             .default_value("ether"))
-        .about("converts an amount into Ether");
-    let to_wei_command = SubCommand::with_name("to-wei")
+        .arg(Arg::with_name("hex")
+            .long("hex")
+            .conflicts_with_all(&["pretty", "sci"])
+            .help("Outputs the amount as a 0x-prefixed hex quantity, as JSON-RPC expects."))
+        .about("converts an amount into Wei"), false)));
+
+    let from_units_cmd = with_negative_arg(with_display_args(with_rounding_args(SubCommand::with_name("from-units")
+        .arg(Arg::with_name("amount")
+            .help("the amount of base units to convert, in decimal or as a 0x-prefixed hex quantity")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("decimals")
+            .long("decimals")
+            .takes_value(true)
+            .required(true)
+            .help("The token's decimals, e.g. 6 for USDC or 8 for WBTC."))
+        .about("converts an amount of a token's base units into whole tokens"), true)));
+    let to_units_cmd = with_negative_arg(with_display_args(with_rounding_args(SubCommand::with_name("to-units")
+        .arg(Arg::with_name("amount")
+            .help("the amount of whole tokens to convert")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("decimals")
+            .long("decimals")
+            .takes_value(true)
+            .required(true)
+            .help("The token's decimals, e.g. 6 for USDC or 8 for WBTC."))
+        .about("converts an amount of whole tokens into the token's base units"), false)));
+
+    let math_cmd = SubCommand::with_name("math")
+        .setting(AppSettings::AllowLeadingHyphen)
+        .arg(Arg::with_name("expression")
+            .help("the expression to evaluate, e.g. \"1 ether + 21000 * 30 gwei\"")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("unit")
+            .long("unit")
+            .takes_value(true)
+            .possible_values(Unit::possible_values())
+            .default_value("wei")
+            .help("The unit to output amounts in."))
+        .arg(Arg::with_name("precision")
+            .long("precision")
+            .takes_value(true)
+            .help("The maximum number of decimal places to output."))
+        .arg(Arg::with_name("round")
+            .long("round")
+            .takes_value(true)
+            .possible_values(Rounding::possible_values())
+            .default_value("half-even")
+            .help("How to round results that are not a whole number of wei, or exceed --precision."))
+        .about("evaluates +, -, *, /, and % on amounts exactly, e.g. \"1 ether + 21000 * 30 gwei\"");
+
+    let to_fiat_cmd = SubCommand::with_name("to-fiat")
         .arg(Arg::with_name("amount")
-            .help("the amount to convert to Wei")
-            .index(1))
+            .help("the amount to convert")
+            .index(1)
+            .required(true))
         .arg(Arg::with_name("unit")
             .help("the input unit")
             .index(2)
             .required(true)
             .possible_values(Unit::possible_values())
             .default_value("ether"))
-        .about("converts an amount into Wei");
+        .arg(Arg::with_name("currency")
+            .long("currency")
+            .takes_value(true)
+            .default_value("usd")
+            .help("The fiat currency to convert to."))
+        .arg(Arg::with_name("source")
+            .long("source")
+            .takes_value(true)
+            .possible_values(&["coingecko", "chainlink"])
+            .default_value("coingecko")
+            .help("Where to fetch the ETH price from."))
+        .arg(Arg::with_name("feed")
+            .long("feed")
+            .takes_value(true)
+            .default_value(CHAINLINK_ETH_USD_FEED)
+            .help("The Chainlink price feed to read when --source is chainlink. Defaults to ETH / USD on mainnet."))
+        .arg(make_rpc_url_arg()
+            .required(false)
+            .required_if("source", "chainlink"))
+        .arg(Arg::with_name("at-price")
+            .long("at-price")
+            .takes_value(true)
+            .help("Uses this price per ether instead of fetching one."))
+        .arg(Arg::with_name("precision")
+            .long("precision")
+            .takes_value(true)
+            .help("The maximum number of decimal places to output. Defaults to 2."))
+        .arg(Arg::with_name("round")
+            .long("round")
+            .takes_value(true)
+            .possible_values(Rounding::possible_values())
+            .default_value("half-even")
+            .help("How to round away decimal places beyond --precision."))
+        .about("converts an amount into fiat currency at the current ETH price");
+
+    let parse_cmd = with_negative_arg(SubCommand::with_name("parse")
+        .setting(AppSettings::AllowLeadingHyphen)
+        .arg(Arg::with_name("amount")
+            .help("the amount to parse, e.g. \"1.5 ether\" or 2gwei. Amounts without a unit are in wei.")
+            .index(1)
+            .required(true))
+        .arg(Arg::with_name("hex")
+            .long("hex")
+            .conflicts_with_all(&["pretty", "sci"])
+            .help("Outputs the amount as a 0x-prefixed hex quantity, as JSON-RPC expects."))
+        .about("converts a human-readable amount with a unit into wei"));
 
     SubCommand::with_name("units")
         .subcommand(from_wei_cmd)
         .subcommand(to_wei_command)
+        .subcommand(from_units_cmd)
+        .subcommand(to_units_cmd)
+        .subcommand(with_display_args(math_cmd))
+        .subcommand(with_display_args(parse_cmd))
+        .subcommand(with_display_args(to_fiat_cmd))
         .about("Convert between Ethereum's various monetary units.")
 }
 
@@ -114,24 +753,59 @@ pub fn execute_units_cmd(matches: &ArgMatches) -> util::Res<String> {
         ("from-wei", Some(sub)) => execute_from_wei_cmd(
             sub.value_of("amount").unwrap(),
             sub.value_of("unit").unwrap(),
+            sub,
         ),
         ("to-wei", Some(sub)) => execute_to_wei_cmd(
             sub.value_of("amount").unwrap(),
             sub.value_of("unit").unwrap(),
+            sub.is_present("hex"),
+            sub,
         ),
+        ("from-units", Some(sub)) => {
+            let decimals = sub.value_of("decimals").unwrap().parse::<u8>()? as usize;
+            let (precision, rounding) = parse_rounding_args(sub)?;
+            let (sign, amount) = split_sign(sub.value_of("amount").unwrap(), sub)?;
+            let amount = parse_units(amount, 0)?;
+            Ok(format_display(with_sign(sign, format_units_rounded(&amount, decimals, precision, &rounding.unwrap_or(Rounding::Down))), None, sub))
+        }
+        ("to-units", Some(sub)) => {
+            let decimals = sub.value_of("decimals").unwrap().parse::<u8>()? as usize;
+            let (_, rounding) = parse_rounding_args(sub)?;
+            let (sign, amount) = split_sign(sub.value_of("amount").unwrap(), sub)?;
+            Ok(format_display(with_sign(sign, parse_units_rounded(amount, decimals, rounding.as_ref())?.to_string()), None, sub))
+        }
+        ("math", Some(sub)) => execute_math_cmd(sub),
+        ("parse", Some(sub)) => {
+            let (sign, amount) = split_sign(sub.value_of("amount").unwrap(), sub)?;
+            let wei = parse_amount(amount)?;
+            if sub.is_present("hex") {
+                Ok(with_sign(sign, format!("0x{:x}", wei)))
+            } else {
+                Ok(format_display(with_sign(sign, wei.to_string()), Some("wei"), sub))
+            }
+        }
+        ("to-fiat", Some(sub)) => execute_to_fiat_cmd(sub),
         (c, _) => Err(CmdError::UnknownSubcommand(String::from(c)).into())
     }
 }
 
-fn execute_from_wei_cmd(amount: &str, unit_str: &str) -> util::Res<String> {
-    let mut amount = Decimal::from_str(amount)?;
-    amount.set_scale(18).expect("Scale exceeds");
+fn execute_from_wei_cmd(amount: &str, unit_str: &str, matches: &ArgMatches) -> util::Res<String> {
+    let (sign, amount) = split_sign(amount, matches)?;
+    let amount = parse_units(amount, 0)?;
     let unit = Unit::from_str(unit_str)?;
-    Ok(unit.convert_from_wei(amount).to_string())
+    let (precision, rounding) = parse_rounding_args(matches)?;
+    let converted = unit.convert_from_wei(&amount, precision, &rounding.unwrap_or(Rounding::Down));
+    Ok(format_display(with_sign(sign, converted), Some(unit_str), matches))
 }
 
-fn execute_to_wei_cmd(amount: &str, unit_str: &str) -> util::Res<String> {
-    let amount = Decimal::from_str(amount)?;
+fn execute_to_wei_cmd(amount: &str, unit_str: &str, hex: bool, matches: &ArgMatches) -> util::Res<String> {
+    let (sign, amount) = split_sign(amount, matches)?;
     let unit = Unit::from_str(unit_str)?;
-    Ok(unit.convert_to_wei(amount).to_string())
-}
\ No newline at end of file
+    let (_, rounding) = parse_rounding_args(matches)?;
+    let wei = unit.convert_to_wei(amount, rounding.as_ref())?;
+    if hex {
+        Ok(with_sign(sign, format!("0x{:x}", wei)))
+    } else {
+        Ok(format_display(with_sign(sign, wei.to_string()), Some("wei"), matches))
+    }
+}
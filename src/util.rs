@@ -1,27 +1,36 @@
 use clap::Arg;
-use std::{io, error};
+use std::{io, error, env, thread};
 use std::io::{Read, Error, ErrorKind};
+use std::path::PathBuf;
 use hex::FromHexError;
 use std::fmt;
 
-pub type Res<T> = std::result::Result<T, Box<error::Error>>;
+pub type Res<T> = std::result::Result<T, Box<dyn error::Error>>;
 
+/// Errors shared by every command. Module-specific failures get their own
+/// error types; everything is surfaced to the user through `Res`.
 #[derive(Debug)]
 pub enum CmdError {
-    UnknownSubcommand(String)
+    UnknownSubcommand(String),
+    InvalidArgument(String),
+    InvalidInput(String),
+    Unavailable(String),
 }
 
 impl fmt::Display for CmdError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
-            CmdError::UnknownSubcommand(c) => write!(f, "unknown command {}", c)
+            CmdError::UnknownSubcommand(c) => write!(f, "unknown command {}", c),
+            CmdError::InvalidArgument(s) => write!(f, "invalid argument: {}", s),
+            CmdError::InvalidInput(s) => write!(f, "invalid input: {}", s),
+            CmdError::Unavailable(s) => write!(f, "{} is unavailable", s),
         }
     }
 }
 
 impl error::Error for CmdError {}
 
-pub fn make_input_arg(help: &str) -> Arg {
+pub fn make_input_arg(help: &str) -> Arg<'_, '_> {
     Arg::with_name("input")
         .short("-i")
         .required(true)
@@ -29,7 +38,19 @@ pub fn make_input_arg(help: &str) -> Arg {
         .help(help)
 }
 
-pub fn read_hex_input(input: &str) -> Result<Vec<u8>, Box<error::Error>> {
+/// Parses a worker thread count, defaulting to the number of available CPUs.
+pub fn parse_thread_count(threads: Option<&str>) -> Res<usize> {
+    let threads: usize = match threads {
+        Some(t) => t.parse()?,
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+    if threads == 0 {
+        return Err(CmdError::InvalidArgument(String::from("thread count must be at least 1")).into());
+    }
+    Ok(threads)
+}
+
+pub fn read_hex_input(input: &str) -> Result<Vec<u8>, Box<dyn error::Error>> {
     if input == "-" {
         let mut vec = Vec::new();
         io::stdin().read_to_end(&mut vec)?;
@@ -57,7 +78,7 @@ pub fn read_raw_input(input: &str) -> Result<Vec<u8>, Error> {
     }
 }
 
-pub fn encode_hex<'a>(input: &Vec<u8>) -> String {
+pub fn encode_hex(input: &[u8]) -> String {
     let out = format!("0x{}", hex::encode(input));
     out.to_owned()
 }
@@ -70,4 +91,36 @@ pub fn decode_hex(input: &str) -> Result<Vec<u8>, FromHexError> {
     };
 
     hex::decode(stripped)
-}
\ No newline at end of file
+}
+
+pub fn http_get_json(url: &str) -> Res<serde_json::Value> {
+    let res = ureq::get(url).call()?;
+    Ok(res.into_json()?)
+}
+
+pub fn data_path(name: &str) -> Res<PathBuf> {
+    let dir = match (env::var_os("ETHTOOL_HOME"), env::var_os("HOME")) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Some(home)) => PathBuf::from(home).join(".ethtool"),
+        (None, None) => return Err(CmdError::InvalidArgument(String::from("could not locate a home directory; set ETHTOOL_HOME")).into()),
+    };
+
+    Ok(dir.join(name))
+}
+
+/// Formats a unix timestamp as an RFC 3339 UTC time, e.g. `2020-12-01T12:00:23Z`.
+pub fn format_utc(secs: u64) -> String {
+    // Converts days since the epoch to a proleptic Gregorian date; see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days.
+    let days = (secs / 86400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day,
+            secs % 86400 / 3600, secs % 3600 / 60, secs % 60)
+}